@@ -16,6 +16,7 @@
 
 from biobear import compression
 from biobear.compression import Compression
+from biobear.progress import TqdmProgress
 
 from .biobear import FileCompressionType
 from .biobear import FastaSequenceDataType
@@ -35,9 +36,13 @@ from .biobear import GenBankReadOptions
 from .biobear import FCSReadOptions
 from .biobear import CRAMReadOptions
 from .biobear import SDFReadOptions
+from .biobear import SessionConfig
+from .biobear import Progress
+from .biobear import PlanningError
+from .biobear import SQLSyntaxError
 from .biobear import connect
 from .biobear import new_session
-from .biobear import __runtime
+from .biobear import configure_runtime
 
 
 __version__ = "0.23.7"
@@ -63,8 +68,13 @@ __all__ = [
     "GTFReadOptions",
     "MzMLReadOptions",
     "HMMDomTabReadOptions",
+    "SessionConfig",
+    "Progress",
+    "TqdmProgress",
+    "PlanningError",
+    "SQLSyntaxError",
     "__version__",
     "connect",
     "new_session",
-    "__runtime",
+    "configure_runtime",
 ]
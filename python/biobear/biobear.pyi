@@ -12,9 +12,10 @@
 # See the License for the specific language governing permissions and
 # limitations under the License.
 
-from pyarrow import Table, RecordBatchStreamReader
-from typing import Optional
+from pyarrow import Array, DataType, RecordBatch, Schema, Table, RecordBatchStreamReader
+from typing import Any, Awaitable, Callable, Literal, Optional, Sequence, Union
 import enum
+import os
 
 POLARS_INSTALLED = False
 try:
@@ -181,98 +182,559 @@ class GFFReadOptions:
         region: Optional[str] = None,
     ) -> None: ...
 
+class PlanningError(ValueError):
+    """Raised when a SQL query can't be planned, e.g. it references an unknown column."""
+
+    line: Optional[int]
+    column: Optional[int]
+
+class SQLSyntaxError(PlanningError):
+    """Raised when a SQL query can't be parsed."""
+
 class ExecutionResult:
     """The result of an execution."""
     def to_arrow(self) -> Table:
         """Converts the result to an Arrow Table."""
+    def to_pandas(self) -> Any:
+        """Converts the result to a pandas DataFrame."""
     def to_arrow_record_batch_reader(self) -> RecordBatchStreamReader:
         """Converts the result to an Arrow RecordBatchStreamReader."""
+    def collect(self) -> list[RecordBatch]:
+        """Collects the result into a list of Arrow RecordBatches."""
+    def collect_async(self) -> Awaitable[list[RecordBatch]]:
+        """Collects the result without blocking the event loop."""
+    def __aiter__(self) -> RecordBatchStream:
+        """Iterates over the result's RecordBatches with `async for`."""
+    def filter(self, predicate: str) -> ExecutionResult:
+        """Returns the rows matching the SQL predicate, e.g. `"start > 100"`."""
+    def select(self, *exprs: str) -> ExecutionResult:
+        """Returns the SQL expressions as columns, e.g. `select("name", "end - start AS len")`."""
+    def with_column(self, name: str, expr: str) -> ExecutionResult:
+        """Adds a column from a SQL expression, replacing any column with the same name."""
+    def sort(self, *exprs: str) -> ExecutionResult:
+        """Sorts by the SQL sort expressions, e.g. `sort("chrom", "pos DESC")`."""
+    def limit(self, n: int, offset: int = 0) -> ExecutionResult:
+        """Returns at most `n` rows, after skipping `offset` rows."""
+    def join(
+        self,
+        right: ExecutionResult,
+        on: Union[str, Sequence[str]],
+        how: Literal["inner", "left", "right", "full", "semi", "anti"] = "inner",
+    ) -> ExecutionResult:
+        """Joins with `right` on a SQL predicate, e.g. `"id = sample_id"`, or shared columns."""
+    def aggregate(
+        self, group_by: Sequence[str], aggregates: Sequence[str]
+    ) -> ExecutionResult:
+        """Groups by the SQL expressions and computes the aggregates, e.g. `"count(*) AS n"`."""
+    def distinct(self) -> ExecutionResult:
+        """Removes duplicate rows."""
+    def union(self, other: ExecutionResult, distinct: bool = False) -> ExecutionResult:
+        """Appends the rows of `other`, whose columns must match, optionally without duplicates."""
+    def count(self) -> int:
+        """Counts the rows, using file statistics where available."""
+    def describe(self) -> ExecutionResult:
+        """Summarizes each column in a row, computed in a single aggregation.
+
+        The summary has the column's name and type, its non-null and null counts, its
+        min and max as strings, its mean if it's numeric, and an estimate of its
+        distinct values if it's a string.
+        """
+    def head(self, n: int = 5) -> ExecutionResult:
+        """Returns the first `n` rows, limiting the plan so e.g. scans stop early."""
+    def show(self, n: int = 20) -> None:
+        """Prints the first `n` rows as a table."""
+    def _repr_html_(self) -> str:
+        """Renders the schema and first rows as HTML, executing only as far as needed."""
+    def __iter__(self) -> RecordBatchIterator:
+        """Iterates over the result's RecordBatches with `for`, executing as they're read."""
+    def iter_batches(self, max_rows: Optional[int] = None) -> RecordBatchIterator:
+        """Iterates over the result's RecordBatches, splitting any over `max_rows` rows.
+
+        Batches are produced as they're read, so a slow consumer holds back the scan
+        rather than the batches accumulating in memory.
+        """
+    def iter_partitions(
+        self, max_rows: Optional[int] = None
+    ) -> list[RecordBatchIterator]:
+        """Executes the query and iterates over each of its output partitions.
+
+        Each iterator can be consumed in its own thread.
+        """
+    def __arrow_c_schema__(self) -> object:
+        """Exports the result's schema as an Arrow PyCapsule."""
+    def __arrow_c_stream__(self, requested_schema: Optional[object] = None) -> object:
+        """Executes the query and exports its batches as an Arrow PyCapsule stream.
+
+        Lets libraries implementing the Arrow PyCapsule interface read the result
+        without pyarrow, e.g. `polars.DataFrame(result)`. The columns are cast to the
+        requested schema where possible, otherwise it's ignored.
+        """
+    def explain(self, analyze: bool = False, verbose: bool = False) -> QueryPlan:
+        """Describes the logical and physical plans of the query.
+
+        With `analyze=True` the query is executed, and the result includes the metrics
+        each operator recorded. With `verbose=True` the plans include their schemas.
+        """
+    def cancel(self) -> None:
+        """Cancels operations running on this result, e.g. `to_polars()` in another thread
+        or an awaited `collect_async()`.
+
+        The cancelled call raises `concurrent.futures.CancelledError`. Pressing Ctrl-C
+        while a result is being collected cancels the query and raises `KeyboardInterrupt`.
+        """
+    def with_progress(
+        self, callback: Callable[[Progress], None], interval: float = 0.5
+    ) -> ExecutionResult:
+        """Returns a copy of the result that reports its progress while it's collected,
+        including with `collect_async()`, while its batches are iterated over, e.g. with
+        `iter_batches()` or `async for`, and while they're read from its Arrow streams.
+
+        The callback is called with a `Progress` at most every `interval` seconds, and
+        once more with `finished=True`. The `read_*_file` methods take the callback as
+        their `progress` argument too, e.g.
+        `session.read_bam_file(path, progress=biobear.TqdmProgress()).to_polars()`.
+        An exception raised by the callback stops the query.
+        """
+    def write_parquet(
+        self,
+        path: str,
+        *,
+        compression: Optional[str] = None,
+        row_group_size: Optional[int] = None,
+        partition_by: Optional[Sequence[str]] = None,
+    ) -> int:
+        """Streams the result to Parquet and returns the number of rows written.
+
+        The path is local or a URL of a registered object store. A path ending in `/`
+        is written as a directory of files, as is one partitioned by the
+        `partition_by` columns, hive-style, e.g. `chrom=1/`. `compression` is a codec
+        such as `snappy` or `zstd(3)`, and `row_group_size` the maximum rows in a
+        row group.
+        """
+    def write_csv(
+        self,
+        path: str,
+        *,
+        compression: Optional[str] = None,
+        partition_by: Optional[Sequence[str]] = None,
+    ) -> int:
+        """Streams the result to CSV with a header and returns the number of rows written.
+
+        The path and `partition_by` are as for `write_parquet`. `compression` is a
+        codec such as `gzip` or `zstd`.
+        """
+    def write_json(
+        self,
+        path: str,
+        *,
+        compression: Optional[str] = None,
+        partition_by: Optional[Sequence[str]] = None,
+    ) -> int:
+        """Streams the result to newline-delimited JSON and returns the rows written.
+
+        The path and `partition_by` are as for `write_parquet`. `compression` is a
+        codec such as `gzip` or `zstd`.
+        """
+    def write_ipc(
+        self, path: str, *, partition_by: Optional[Sequence[str]] = None
+    ) -> int:
+        """Streams the result to LZ4-compressed Arrow IPC files and returns the rows written.
+
+        The path and `partition_by` are as for `write_parquet`.
+        """
 
     if POLARS_INSTALLED:
-        def to_polars(self) -> pl.DataFrame:
-            """Converts the result to a Polars DataFrame."""
+        def to_polars(self, lazy: bool = False) -> Union[pl.DataFrame, pl.LazyFrame]:
+            """Converts the result to a Polars DataFrame, or a LazyFrame with `lazy=True`.
+
+            The LazyFrame executes the query when it's collected. Its column selections,
+            filters that can be written as SQL, and row limits, e.g. from `head()`, are
+            pushed down into the query before any data is read.
+            """
+
+class OperatorMetrics:
+    """The metrics recorded by an operator of an analyzed query, summed over its partitions."""
+
+    operator: str
+    description: str
+    depth: int
+    output_rows: Optional[int]
+    elapsed_compute: Optional[float]
+    bytes_scanned: Optional[int]
+    files_pruned: Optional[int]
+    metrics: dict[str, int]
+
+class QueryPlan:
+    """The plans of a query, returned by `ExecutionResult.explain`."""
+
+    logical_plan: str
+    physical_plan: str
+    metrics: Optional[list[OperatorMetrics]]
+    files_opened: Optional[int]
+    bytes_read: Optional[int]
+
+class Progress:
+    """A snapshot of a query's progress, passed to `ExecutionResult.with_progress` callbacks."""
+
+    files_opened: int
+    bytes_read: int
+    rows: int
+    partitions_completed: int
+    partitions_total: int
+    elapsed: float
+    finished: bool
+
+class RecordBatchStream:
+    """An async iterator over the RecordBatches of a query."""
+    def __aiter__(self) -> RecordBatchStream: ...
+    def __anext__(self) -> Awaitable[RecordBatch]: ...
+
+class RecordBatchIterator:
+    """An iterator over the RecordBatches of a query."""
+    def __iter__(self) -> RecordBatchIterator: ...
+    def __next__(self) -> RecordBatch: ...
+
+class SessionConfig:
+    """Options for constructing a session."""
+    def __init__(
+        self,
+        *,
+        batch_size: Optional[int] = None,
+        target_partitions: Optional[int] = None,
+        memory_limit: Optional[int] = None,
+        memory_fraction: Optional[float] = None,
+        spill_path: Optional[str] = None,
+        repartition_joins: Optional[bool] = None,
+        repartition_aggregations: Optional[bool] = None,
+        repartition_windows: Optional[bool] = None,
+        repartition_file_scans: Optional[bool] = None,
+    ) -> None: ...
+
+class PreparedQuery:
+    """A query planned once and executed with different parameters."""
+    def execute(
+        self, params: Optional[Union[Sequence[Any], dict[str, Any]]] = None
+    ) -> ExecutionResult:
+        """Binds the parameters and returns an ExecutionResult."""
+    def parameter_types(self) -> dict[str, DataType]:
+        """Returns the inferred types of the query's placeholders."""
 
 class BioBearSessionContext:
-    def __init__(self) -> None: ...
+    def __init__(self, config: Optional[SessionConfig] = None) -> None: ...
     def read_fastq_file(
-        self, file_path: str, /, options: Optional[FASTQReadOptions] = None
+        self,
+        file_path: str,
+        /,
+        options: Optional[FASTQReadOptions] = None,
+        *,
+        progress: Optional[Callable[[Progress], None]] = None,
     ) -> ExecutionResult:
         """Reads one or more FASTQ files and returns an ExecutionResult."""
     def read_sdf_file(
-        self, file_path: str, /, options: Optional[SDFReadOptions] = None
+        self,
+        file_path: str,
+        /,
+        options: Optional[SDFReadOptions] = None,
+        *,
+        progress: Optional[Callable[[Progress], None]] = None,
     ) -> ExecutionResult:
         """Reads one or more SDF files and returns an ExecutionResult."""
     def read_fasta_file(
-        self, file_path: str, /, options: Optional[FASTAReadOptions] = None
+        self,
+        file_path: str,
+        /,
+        options: Optional[FASTAReadOptions] = None,
+        *,
+        progress: Optional[Callable[[Progress], None]] = None,
     ) -> ExecutionResult:
         """Reads one or more FASTA files and returns an ExecutionResult."""
     def read_vcf_file(
-        self, file_path: str, /, options: Optional[VCFReadOptions] = None
+        self,
+        file_path: str,
+        /,
+        options: Optional[VCFReadOptions] = None,
+        *,
+        progress: Optional[Callable[[Progress], None]] = None,
     ) -> ExecutionResult:
         """Reads one or more VCF files and returns an ExecutionResult."""
     def read_bcf_file(
-        self, file_path: str, /, options: Optional[BCFReadOptions] = None
+        self,
+        file_path: str,
+        /,
+        options: Optional[BCFReadOptions] = None,
+        *,
+        progress: Optional[Callable[[Progress], None]] = None,
     ) -> ExecutionResult:
         """Reads one or more BCF files and returns an ExecutionResult."""
     def read_sam_file(
-        self, file_path: str, /, options: Optional[SAMReadOptions] = None
+        self,
+        file_path: str,
+        /,
+        options: Optional[SAMReadOptions] = None,
+        *,
+        progress: Optional[Callable[[Progress], None]] = None,
     ) -> ExecutionResult:
         """Reads a SAM file and returns an ExecutionResult."""
     def read_bam_file(
-        self, file_path: str, /, options: Optional[BAMReadOptions] = None
+        self,
+        file_path: str,
+        /,
+        options: Optional[BAMReadOptions] = None,
+        *,
+        progress: Optional[Callable[[Progress], None]] = None,
     ) -> ExecutionResult:
         """Reads a BAM file and returns an ExecutionResult."""
     def read_bed_file(
-        self, file_path: str, /, options: Optional[BEDReadOptions] = None
+        self,
+        file_path: str,
+        /,
+        options: Optional[BEDReadOptions] = None,
+        *,
+        progress: Optional[Callable[[Progress], None]] = None,
     ) -> ExecutionResult:
         """Reads a BED file and returns an ExecutionResult."""
     def read_bigwig_file(
-        self, file_path: str, /, options: Optional[BigWigReadOptions] = None
+        self,
+        file_path: str,
+        /,
+        options: Optional[BigWigReadOptions] = None,
+        *,
+        progress: Optional[Callable[[Progress], None]] = None,
     ) -> ExecutionResult:
         """Reads a BigWig file and returns an ExecutionResult."""
     def read_gff_file(
-        self, file_path: str, /, options: Optional[GFFReadOptions] = None
+        self,
+        file_path: str,
+        /,
+        options: Optional[GFFReadOptions] = None,
+        *,
+        progress: Optional[Callable[[Progress], None]] = None,
     ) -> ExecutionResult:
         """Reads a GFF file and returns an ExecutionResult."""
     def read_gtf_file(
-        self, file_path: str, /, options: Optional[GTFReadOptions] = None
+        self,
+        file_path: str,
+        /,
+        options: Optional[GTFReadOptions] = None,
+        *,
+        progress: Optional[Callable[[Progress], None]] = None,
     ) -> ExecutionResult:
         """Reads a GTF file and returns an ExecutionResult."""
     def read_mzml_file(
-        self, file_path: str, /, options: Optional[MzMLReadOptions] = None
+        self,
+        file_path: str,
+        /,
+        options: Optional[MzMLReadOptions] = None,
+        *,
+        progress: Optional[Callable[[Progress], None]] = None,
     ) -> ExecutionResult:
         """Reads a mzML file and returns an ExecutionResult."""
     def read_genbank_file(
-        self, file_path: str, /, options: Optional[GenBankReadOptions] = None
+        self,
+        file_path: str,
+        /,
+        options: Optional[GenBankReadOptions] = None,
+        *,
+        progress: Optional[Callable[[Progress], None]] = None,
     ) -> ExecutionResult:
         """Reads a GenBank file and returns an ExecutionResult."""
     def read_cram_file(
-        self, file_path: str, /, options: Optional[CRAMReadOptions] = None
+        self,
+        file_path: str,
+        /,
+        options: Optional[CRAMReadOptions] = None,
+        *,
+        progress: Optional[Callable[[Progress], None]] = None,
     ) -> ExecutionResult:
         """Reads a CRAM file and returns an ExecutionResult."""
     def read_fcs_file(
         self, file_path: str, /, options: Optional[FCSReadOptions] = None
     ) -> ExecutionResult:
         """Reads a FCS file and returns an ExecutionResult."""
-    def sql(self, query: str) -> ExecutionResult:
-        """Executes a SQL query and returns an ExecutionResult."""
-    def execute(self, query: str) -> None:
-        """Executes a SQL query."""
+    def sql(
+        self, query: str, params: Optional[Union[Sequence[Any], dict[str, Any]]] = None
+    ) -> ExecutionResult:
+        """Executes a SQL query and returns an ExecutionResult.
+
+        Placeholders are bound to `params`, either a list for positional parameters
+        (`$1`, `$2`) or a dict for named parameters (`$region`).
+        """
+    def plan(
+        self, query: str, params: Optional[Union[Sequence[Any], dict[str, Any]]] = None
+    ) -> Schema:
+        """Plans a SQL query without executing it and returns the schema of its result.
+
+        Raises `SQLSyntaxError` if the query can't be parsed, or `PlanningError` if it
+        can't be planned. DDL statements are planned but not run.
+        """
+    def validate(
+        self, query: str, params: Optional[Union[Sequence[Any], dict[str, Any]]] = None
+    ) -> Optional[PlanningError]:
+        """Checks a SQL query can be planned, returning the error it would raise, or None."""
+    def execute_script(self, script: Union[str, os.PathLike]) -> ExecutionResult:
+        """Runs the statements of a SQL script in order and returns the result of the last.
+
+        `script` is either SQL text as a `str`, or the path of a file containing it as an
+        `os.PathLike`, e.g. a `pathlib.Path`. Every statement but the last is executed. If
+        a statement fails, its error is raised with the statement's position in the script
+        and `statement_index` and `statement` attributes.
+
+        Statements are parsed with DataFusion's generic dialect, as `sql` parses them,
+        whatever the session's `datafusion.sql_parser.dialect`.
+        """
+    def set(self, key: str, value: Union[str, int, float, bool]) -> None:
+        """Sets a DataFusion or Exon option for subsequent queries.
+
+        For example `datafusion.execution.batch_size` or `exon.vcf_parse_info`. Raises
+        `ValueError` if the option doesn't exist or the value isn't valid for it.
+        """
+    def get(self, key: str) -> Optional[str]:
+        """Gets the value of an option as a string, or None if it's unset."""
+    def settings(self) -> dict[str, Optional[str]]:
+        """Gets all the options, mapping each key to its value, or None if it's unset."""
+    def sql_async(
+        self, query: str, params: Optional[Union[Sequence[Any], dict[str, Any]]] = None
+    ) -> Awaitable[ExecutionResult]:
+        """Plans a SQL query without blocking the event loop."""
+    def prepare(self, query: str) -> PreparedQuery:
+        """Plans a SQL query once so it can be executed with different parameters."""
+    def execute(
+        self, query: str, params: Optional[Union[Sequence[Any], dict[str, Any]]] = None
+    ) -> None:
+        """Executes a SQL query, binding any placeholders to `params`."""
+    def execute_async(
+        self, query: str, params: Optional[Union[Sequence[Any], dict[str, Any]]] = None
+    ) -> Awaitable[None]:
+        """Executes a SQL query without blocking the event loop."""
+    def catalogs(self) -> list[str]:
+        """Lists the catalogs registered with the session."""
+    def schemas(self, catalog: Optional[str] = None) -> list[str]:
+        """Lists the schemas in a catalog, defaulting to the session's catalog."""
+    def tables(
+        self, catalog: Optional[str] = None, schema: Optional[str] = None
+    ) -> list[str]:
+        """Lists the tables in a schema, defaulting to the session's schema."""
+    def table_exists(self, name: str) -> bool:
+        """Checks if a table exists."""
+    def table_schema(self, name: str) -> Schema:
+        """Returns the Arrow schema of a table."""
+    def deregister_table(self, name: str) -> bool:
+        """Deregisters a table, returning True if a table was removed."""
+    def register_udf(
+        self,
+        name: str,
+        func: Callable[..., Array],
+        input_types: list[DataType],
+        return_type: DataType,
+        volatility: Literal["immutable", "stable", "volatile"] = "volatile",
+    ) -> None:
+        """Registers a Python function as a scalar UDF.
+
+        The function is called once per batch with a pyarrow Array for each argument
+        and must return a pyarrow Array of the same length.
+        """
+    def register_udaf(
+        self,
+        name: str,
+        accumulator: Callable[[], Any],
+        input_types: list[DataType],
+        return_type: DataType,
+        state_types: list[DataType],
+        volatility: Literal["immutable", "stable", "volatile"] = "volatile",
+    ) -> None:
+        """Registers a Python accumulator class as an aggregate UDF.
+
+        The class is instantiated per group and must implement `update(*values)`,
+        `merge(*states)`, `state()` and `evaluate()`. `update` and `merge` receive
+        pyarrow Arrays, `state` returns one value per state type, and `evaluate`
+        returns the final value.
+        """
+    def register_udtf(self, name: str, func: Callable[..., Any]) -> None:
+        """Registers a Python function as a table function.
+
+        The function is called with the literal arguments from the query, e.g.
+        `SELECT * FROM my_scan('path')`, and may return a RecordBatchReader, an
+        iterator of RecordBatches, or anything accepted by `register_table`.
+        """
+    def register_table(self, name: str, obj: Any) -> None:
+        """Registers an in-memory object as a table.
+
+        Accepts a pyarrow Table or RecordBatchReader, an iterator of RecordBatches, a
+        Polars or pandas DataFrame, or any object implementing the Arrow PyCapsule
+        stream interface. Readers and iterators are registered as streaming tables and
+        can only be scanned once.
+        """
+    def register_object_store_from_url(self, url: str) -> None:
+        """Registers an object store for the URL using credentials from the environment."""
+    def register_s3(
+        self,
+        bucket: str,
+        endpoint: Optional[str] = None,
+        region: Optional[str] = None,
+        access_key: Optional[str] = None,
+        secret_key: Optional[str] = None,
+        session_token: Optional[str] = None,
+        allow_http: bool = False,
+    ) -> None:
+        """Registers an S3 or S3-compatible (e.g. MinIO) bucket for this session.
+
+        Without credentials or an endpoint the `AWS_*` environment variables are used,
+        and with them the environment is ignored.
+        """
+    def register_gcs(
+        self,
+        bucket: str,
+        service_account_path: Optional[str] = None,
+        service_account_key: Optional[str] = None,
+    ) -> None:
+        """Registers a Google Cloud Storage bucket for this session.
+
+        Without a service account the `GOOGLE_*` environment variables are used, and
+        with one the environment is ignored.
+        """
+    def register_azure(
+        self,
+        account: str,
+        container: str,
+        access_key: Optional[str] = None,
+        endpoint: Optional[str] = None,
+        allow_http: bool = False,
+    ) -> None:
+        """Registers an Azure Blob Storage container as `az://<container>` for this session.
+
+        Without an access key or an endpoint the `AZURE_*` environment variables are
+        used, and with them the environment is ignored.
+        """
+
+def configure_runtime(
+    *, worker_threads: Optional[int] = None, max_blocking_threads: Optional[int] = None
+) -> None:
+    """Configures the thread pool shared by all queries and readers.
+
+    Overrides the `BIOBEAR_WORKER_THREADS` and `BIOBEAR_MAX_BLOCKING_THREADS` environment
+    variables. Must be called before running any queries, otherwise raises `ValueError`.
+    """
 
-def connect() -> BioBearSessionContext:
+def connect(config: Optional[SessionConfig] = None) -> BioBearSessionContext:
     """Connect to the BioBear server and return a session context.
 
     Note:
         This function is deprecated. Use `new_session` instead.
 
+    Args:
+        config: Optional configuration for the session.
+
     Returns:
         BioBearSessionContext: A session context for interacting with the BioBear server.
 
     """
 
-def new_session() -> BioBearSessionContext:
+def new_session(config: Optional[SessionConfig] = None) -> BioBearSessionContext:
     """Create a new session context for interacting with the BioBear server.
 
+    Args:
+        config: Optional configuration for the session.
+
     Returns:
         BioBearSessionContext: A session context for interacting with the BioBear server.
 
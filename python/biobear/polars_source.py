@@ -0,0 +1,320 @@
+# Copyright 2024 WHERE TRUE Technologies.
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#     http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+
+"""A lazy Polars source backed by a query, for `ExecutionResult.to_polars(lazy=True)`.
+
+Polars' projections, filters and row limits are pushed down into the query before it's
+executed, so e.g. a filter on a column the scan can prune by reads only the matching
+files or regions.
+"""
+
+import json
+import warnings
+from typing import TYPE_CHECKING, Any, Iterator, Optional
+
+import polars as pl
+from polars.io.plugins import register_io_source
+
+from .biobear import PlanningError
+
+if TYPE_CHECKING:
+    from .biobear import ExecutionResult
+
+# Comparison operators as Polars serializes them, and their SQL equivalents.
+_COMPARISONS = {
+    "Eq": "=",
+    "NotEq": "<>",
+    "Lt": "<",
+    "LtEq": "<=",
+    "Gt": ">",
+    "GtEq": ">=",
+}
+
+_CONJUNCTIONS = {
+    "And": "AND",
+    "LogicalAnd": "AND",
+    "Or": "OR",
+    "LogicalOr": "OR",
+}
+
+_INTEGER_TYPES = {
+    "Int",
+    "Int8",
+    "Int16",
+    "Int32",
+    "Int64",
+    "UInt8",
+    "UInt16",
+    "UInt32",
+    "UInt64",
+}
+
+_FLOAT_TYPES = {"Float", "Float32", "Float64"}
+
+_STRING_TYPES = {"String", "Str", "StringOwned"}
+
+# Marks a literal that can't be written as SQL.
+_UNSUPPORTED = object()
+
+
+def scan_execution_result(result: "ExecutionResult") -> pl.LazyFrame:
+    """Return a LazyFrame that executes the result's query when it's collected.
+
+    The columns Polars selects, the parts of its filters that can be written as SQL,
+    and its row limits, e.g. from `head()`, are added to the query. Filters that can't
+    be are applied by Polars to the batches as they're read.
+    """
+    schema = pl.from_arrow(result.schema().empty_table()).schema
+    boolean_columns = {name for name, dtype in schema.items() if dtype == pl.Boolean}
+
+    def source(
+        with_columns: Optional[list[str]],
+        predicate: Optional[pl.Expr],
+        n_rows: Optional[int],
+        batch_size: Optional[int],
+    ) -> Iterator[pl.DataFrame]:
+        query = result
+        exact = True
+
+        if predicate is not None:
+            filters, exact = predicate_to_sql(predicate, boolean_columns)
+
+            for sql in filters:
+                try:
+                    query = query.filter(sql)
+                except PlanningError:
+                    exact = False
+
+        if with_columns is not None:
+            query = query.select(*(_quote_identifier(name) for name in with_columns))
+
+        # The limit can only be applied before the rows are filtered if they all are.
+        if n_rows is not None and exact:
+            query = query.limit(n_rows)
+
+        remaining = n_rows
+        if remaining == 0:
+            return
+
+        for batch in query.iter_batches(max_rows=batch_size or None):
+            df = pl.from_arrow(batch)
+
+            if not exact:
+                df = df.filter(predicate)
+
+            if remaining is not None:
+                df = df.head(remaining)
+                remaining -= df.height
+
+            yield df
+
+            if remaining == 0:
+                return
+
+    return register_io_source(source, schema=schema)
+
+
+def predicate_to_sql(
+    predicate: pl.Expr, boolean_columns: set[str]
+) -> tuple[list[str], bool]:
+    """Translate the parts of a Polars predicate joined by `&` to SQL filters.
+
+    Returns the filters and whether together they're the whole predicate. Parts that
+    use expressions without an equivalent SQL filter are left out, so the rows the
+    filters select are a superset of those the predicate does.
+    """
+    try:
+        with warnings.catch_warnings():
+            # Polars warns that the JSON form isn't stable between versions, which is
+            # why anything unrecognized isn't pushed down.
+            warnings.simplefilter("ignore")
+            tree = json.loads(predicate.meta.serialize(format="json"))
+    except (pl.exceptions.PolarsError, TypeError, ValueError):
+        return [], False
+
+    filters = []
+    exact = True
+
+    for conjunct in _conjuncts(tree):
+        sql = _boolean_sql(conjunct, boolean_columns)
+
+        if sql is None:
+            exact = False
+        else:
+            filters.append(sql)
+
+    return filters, exact
+
+
+def _conjuncts(node: Any) -> list[Any]:
+    binary = _unwrap(node, "BinaryExpr")
+
+    if binary is not None and binary.get("op") in ("And", "LogicalAnd"):
+        return _conjuncts(binary["left"]) + _conjuncts(binary["right"])
+
+    return [node]
+
+
+def _boolean_sql(node: Any, boolean_columns: set[str]) -> Optional[str]:
+    """Translate a boolean expression, or return None if it can't be."""
+    column = _unwrap(node, "Column")
+    if isinstance(column, str):
+        return _quote_identifier(column) if column in boolean_columns else None
+
+    literal = _literal(node)
+    if isinstance(literal, bool):
+        return "TRUE" if literal else "FALSE"
+
+    binary = _unwrap(node, "BinaryExpr")
+    if binary is not None:
+        op = binary.get("op")
+
+        if op in _COMPARISONS:
+            sql_op = _COMPARISONS[op]
+            left = _value_sql(binary.get("left"))
+            right = _value_sql(binary.get("right"))
+        elif op in _CONJUNCTIONS:
+            sql_op = _CONJUNCTIONS[op]
+            left = _boolean_sql(binary.get("left"), boolean_columns)
+            right = _boolean_sql(binary.get("right"), boolean_columns)
+        else:
+            return None
+
+        if left is None or right is None:
+            return None
+
+        return f"({left} {sql_op} {right})"
+
+    function = _unwrap(node, "Function")
+    if function is not None:
+        name = _boolean_function(function.get("function"))
+        inputs = function.get("input") or []
+
+        if len(inputs) != 1:
+            return None
+
+        if name == "Not":
+            operand = _boolean_sql(inputs[0], boolean_columns)
+            return None if operand is None else f"(NOT {operand})"
+
+        if name in ("IsNull", "IsNotNull"):
+            operand = _value_sql(inputs[0])
+            keyword = "IS NULL" if name == "IsNull" else "IS NOT NULL"
+            return None if operand is None else f"({operand} {keyword})"
+
+    return None
+
+
+def _value_sql(node: Any) -> Optional[str]:
+    """Translate a column or literal, or return None if it's any other expression."""
+    column = _unwrap(node, "Column")
+    if isinstance(column, str):
+        return _quote_identifier(column)
+
+    value = _literal(node)
+
+    if value is _UNSUPPORTED:
+        return None
+    if value is None:
+        return "NULL"
+    if isinstance(value, bool):
+        return "TRUE" if value else "FALSE"
+    if isinstance(value, int):
+        return str(value) if -(2**63) <= value < 2**63 else None
+    if isinstance(value, float):
+        return repr(value) if abs(value) != float("inf") else None
+
+    return "'" + value.replace("'", "''") + "'"
+
+
+def _literal(node: Any) -> Any:
+    """Decode a literal, or a numeric literal cast to another numeric type."""
+    literal = _unwrap(node, "Literal")
+    if literal is not None:
+        return _literal_value(literal)
+
+    cast = _unwrap(node, "Cast")
+    dtype = cast.get("dtype") if isinstance(cast, dict) else None
+    if isinstance(dtype, str) and dtype in _INTEGER_TYPES | _FLOAT_TYPES:
+        value = _literal(cast.get("expr"))
+
+        if isinstance(value, bool) or not isinstance(value, (int, float)):
+            return _UNSUPPORTED
+        if dtype in _FLOAT_TYPES:
+            return float(value)
+        # Casting a float to an integer truncates it, which isn't worth reproducing.
+        return value if isinstance(value, int) else _UNSUPPORTED
+
+    return _UNSUPPORTED
+
+
+def _literal_value(value: Any) -> Any:
+    """Decode a literal value, whose form varies between Polars versions.
+
+    e.g. `{"Int": 1}`, `{"Dyn": {"Int": 1}}` or
+    `{"Scalar": {"dtype": "Int64", "value": {"Int64": 1}}}`.
+    """
+    if value == "Null":
+        return None
+
+    if not isinstance(value, dict) or len(value) != 1:
+        return _UNSUPPORTED
+
+    ((kind, inner),) = value.items()
+
+    if kind == "Dyn":
+        return _literal_value(inner)
+    if kind == "Scalar":
+        if not isinstance(inner, dict):
+            return _UNSUPPORTED
+        return _literal_value(inner.get("value"))
+    if kind == "Null":
+        return None
+    if kind == "Boolean" and isinstance(inner, bool):
+        return inner
+    if isinstance(inner, bool):
+        return _UNSUPPORTED
+    if kind in _INTEGER_TYPES and isinstance(inner, int):
+        return inner
+    # NaN is serialized as null, so a null float isn't taken to be NULL.
+    if kind in _FLOAT_TYPES and isinstance(inner, (int, float)):
+        return float(inner)
+    if kind in _STRING_TYPES and isinstance(inner, str):
+        return inner
+
+    return _UNSUPPORTED
+
+
+def _boolean_function(function: Any) -> Optional[str]:
+    """Return the name of a boolean function, e.g. `{"Boolean": "IsNull"}`."""
+    if not isinstance(function, dict):
+        return None
+
+    name = function.get("Boolean")
+
+    if isinstance(name, dict) and len(name) == 1:
+        (name,) = name
+
+    return name if isinstance(name, str) else None
+
+
+def _unwrap(node: Any, kind: str) -> Any:
+    if isinstance(node, dict) and len(node) == 1:
+        return node.get(kind)
+
+    return None
+
+
+def _quote_identifier(name: str) -> str:
+    return '"' + name.replace('"', '""') + '"'
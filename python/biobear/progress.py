@@ -0,0 +1,53 @@
+# Copyright 2024 WHERE TRUE Technologies.
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#     http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+
+"""Progress bars for query execution."""
+
+from typing import Any
+
+from .biobear import Progress
+
+
+class TqdmProgress:
+    """A progress callback that renders a tqdm bar over the scan partitions.
+
+    Pass an instance to `ExecutionResult.with_progress`, e.g.
+    `session.read_bam_file(path).with_progress(TqdmProgress()).to_polars()`. Keyword
+    arguments are forwarded to `tqdm.tqdm`.
+    """
+
+    def __init__(self, **kwargs: Any):
+        """Create the callback; the bar is created on the first update."""
+        self._kwargs = {"unit": "partition", **kwargs}
+        self._bar = None
+
+    def __call__(self, progress: Progress) -> None:
+        """Update the bar with the latest progress."""
+        if self._bar is None:
+            from tqdm.auto import tqdm
+
+            self._bar = tqdm(total=progress.partitions_total, **self._kwargs)
+
+        self._bar.n = progress.partitions_completed
+        self._bar.set_postfix(
+            files=progress.files_opened,
+            rows=progress.rows,
+            bytes=progress.bytes_read,
+            refresh=False,
+        )
+        self._bar.refresh()
+
+        if progress.finished:
+            self._bar.close()
+            self._bar = None
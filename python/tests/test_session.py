@@ -14,6 +14,14 @@
 """Test the session context."""
 
 from pathlib import Path
+import asyncio
+import concurrent.futures
+import os
+import shutil
+import signal
+import sys
+import threading
+import time
 import importlib
 import tempfile
 import polars as pl
@@ -35,6 +43,11 @@ from biobear import (
     MzMLReadOptions,
     CRAMReadOptions,
     new_session,
+    SessionConfig,
+    TqdmProgress,
+    PlanningError,
+    SQLSyntaxError,
+    configure_runtime,
 )
 
 DATA = Path(__file__).parent / "data"
@@ -55,6 +68,36 @@ def test_connect_and_to_arrow():
     assert len(arrow_table) == 2
 
 
+def test_new_session_with_config(tmp_path):
+    """Test creating a session with a config."""
+    config = SessionConfig(
+        batch_size=1,
+        target_partitions=2,
+        memory_limit=1024 * 1024 * 1024,
+        memory_fraction=0.5,
+        spill_path=str(tmp_path),
+        repartition_joins=False,
+    )
+    session = new_session(config)
+
+    batches = session.read_fasta_file((DATA / "test.fasta").as_posix()).collect()
+
+    assert sum(b.num_rows for b in batches) == 2
+    assert all(b.num_rows <= 1 for b in batches)
+
+
+def test_new_session_with_invalid_config():
+    """Test that a bad config raises an error instead of panicking."""
+    with pytest.raises(ValueError):
+        SessionConfig(batch_size=0)
+
+    with pytest.raises(ValueError):
+        SessionConfig(memory_fraction=0.5)
+
+    with pytest.raises(ValueError):
+        new_session(SessionConfig(spill_path="/does/not/exist"))
+
+
 @pytest.mark.skipif(
     not importlib.util.find_spec("polars"), reason="polars not installed"
 )
@@ -74,6 +117,67 @@ def test_to_polars_lazy():
     assert len(df.collect()) == 2
 
 
+@pytest.mark.skipif(
+    not importlib.util.find_spec("polars"), reason="polars not installed"
+)
+def test_to_polars_lazy_pushdown():
+    """Test the lazy frame's projections, filters and limits are applied."""
+    session = connect()
+
+    gff_path = (DATA / "test.gff").as_posix()
+    lazy = session.read_gff_file(gff_path).to_polars(lazy=True)
+
+    df = lazy.filter(pl.col("seqname") == "sq1").select("seqname", "start").collect()
+    assert df.to_dict(as_series=False) == {"seqname": ["sq1"], "start": [8]}
+
+    assert lazy.head(1).collect().height == 1
+
+    # Filters that can't be written as SQL are applied by Polars.
+    df = lazy.filter((pl.col("end") - pl.col("start")) > 5).head(1).collect()
+    assert df["seqname"].to_list() == ["sq1"]
+
+
+@pytest.mark.skipif(
+    not importlib.util.find_spec("polars"), reason="polars not installed"
+)
+def test_to_polars_lazy_without_io_plugins(monkeypatch):
+    """Test lazy=True falls back to scanning the collected result without IO plugins."""
+    # Polars before 1.0 has no `polars.io.plugins`.
+    monkeypatch.setitem(sys.modules, "polars.io.plugins", None)
+    monkeypatch.delitem(sys.modules, "biobear.polars_source", raising=False)
+
+    session = connect()
+
+    gff_path = (DATA / "test.gff").as_posix()
+    lazy = session.read_gff_file(gff_path).to_polars(lazy=True)
+
+    assert isinstance(lazy, pl.LazyFrame)
+    assert lazy.filter(pl.col("seqname") == "sq1").collect().height == 1
+
+
+@pytest.mark.skipif(
+    not importlib.util.find_spec("polars"), reason="polars not installed"
+)
+def test_polars_predicate_to_sql():
+    """Test Polars predicates are translated to SQL filters where they can be."""
+    from biobear.polars_source import predicate_to_sql
+
+    predicate = (pl.col("start") > 8) & (pl.col("seqname") == "it's")
+    assert predicate_to_sql(predicate, set()) == (
+        ['("start" > 8)', "(\"seqname\" = 'it''s')"],
+        True,
+    )
+
+    predicate = (pl.col("start") > 8) & ((pl.col("end") - pl.col("start")) > 5)
+    assert predicate_to_sql(predicate, set()) == (['("start" > 8)'], False)
+
+    predicate = pl.col("flag") | pl.col("seqname").is_null()
+    assert predicate_to_sql(predicate, {"flag"}) == (
+        ['("flag" OR ("seqname" IS NULL))'],
+        True,
+    )
+
+
 @pytest.mark.skipif(
     not importlib.util.find_spec("polars"), reason="polars not installed"
 )
@@ -425,29 +529,1227 @@ def test_to_record_batch_reader():
     assert rows == 2
 
 
-def test_read_from_s3():
-    """Test reading from s3."""
+def test_register_arrow_table():
+    """Test registering a pyarrow Table and joining it against a file."""
+    import pyarrow as pa
+
+    session = new_session()
+
+    samples = pa.table({"id": ["a", "b"], "sample": ["s1", "s2"]})
+    session.register_table("samples", samples)
+
+    fasta_path = DATA / "test.fasta"
+    result = session.sql(
+        f"""
+        SELECT f.id, s.sample
+        FROM fasta_scan('{fasta_path}') f
+        JOIN samples s ON f.id = s.id
+        ORDER BY f.id
+        """
+    ).to_arrow()
+
+    assert result.column("sample").to_pylist() == ["s1", "s2"]
+
+    # The table can be scanned more than once
+    assert len(session.sql("SELECT * FROM samples").to_arrow()) == 2
+
+
+def test_register_record_batch_reader():
+    """Test registering a RecordBatchReader as a one-shot streaming table."""
+    import pyarrow as pa
+
+    session = new_session()
+
+    schema = pa.schema([("x", pa.int64())])
+    batches = (pa.record_batch([pa.array([i, i + 1])], schema=schema) for i in range(3))
+    reader = pa.RecordBatchReader.from_batches(schema, batches)
+
+    session.register_table("stream", reader)
+
+    result = session.sql("SELECT SUM(x) AS total FROM stream").to_arrow()
+    assert result.column("total").to_pylist() == [9]
+
+    with pytest.raises(Exception):
+        session.sql("SELECT * FROM stream").to_arrow()
+
+
+@pytest.mark.skipif(
+    not importlib.util.find_spec("polars"), reason="polars not installed"
+)
+def test_register_polars_dataframe():
+    """Test registering a Polars DataFrame."""
+    session = new_session()
+
+    df = pl.DataFrame({"chrom": ["1", "1", "2"], "pos": [1, 2, 3]})
+    session.register_table("positions", df)
+
+    result = session.sql(
+        "SELECT chrom, COUNT(*) AS n FROM positions GROUP BY chrom ORDER BY chrom"
+    ).to_polars()
+
+    assert result.get_column("n").to_list() == [2, 1]
+
+
+@pytest.mark.skipif(
+    not importlib.util.find_spec("pandas"), reason="pandas not installed"
+)
+def test_register_pandas_dataframe():
+    """Test registering a pandas DataFrame."""
+    import pandas as pd
+
+    session = new_session()
+
+    df = pd.DataFrame({"sample": ["s1", "s2"], "qc_pass": [True, False]})
+    session.register_table("qc", df)
+
+    result = session.sql("SELECT sample FROM qc WHERE qc_pass").to_arrow()
+
+    assert result.column("sample").to_pylist() == ["s1"]
+
+
+def test_register_table_invalid_object():
+    """Test registering an object that isn't Arrow compatible."""
+    session = new_session()
+
+    with pytest.raises(TypeError):
+        session.register_table("bad", object())
+
+
+def test_catalog_introspection():
+    """Test listing and dropping tables registered with the session."""
+    session = new_session()
+
+    assert session.catalogs() == ["public"]
+    assert session.schemas() == ["exon"]
+    assert session.tables() == []
+
+    gff_path = DATA / "test.gff"
+    session.execute(f"CREATE EXTERNAL TABLE gff_file STORED AS GFF LOCATION '{gff_path}'")
+
+    assert session.tables() == ["gff_file"]
+    assert session.table_exists("gff_file")
+    assert not session.table_exists("missing")
+
+    schema = session.table_schema("gff_file")
+    assert schema.names[0] == "seqname"
+
+    assert session.deregister_table("gff_file")
+    assert not session.deregister_table("gff_file")
+    assert session.tables() == []
+
+    with pytest.raises(Exception):
+        session.tables(catalog="missing")
+
+
+def test_register_udf():
+    """Test registering a Python scalar UDF."""
+    import pyarrow as pa
+    import pyarrow.compute as pc
+
+    session = new_session()
+
+    def barcode(sequence: pa.Array) -> pa.Array:
+        return pc.utf8_slice_codeunits(sequence, 0, 4)
+
+    session.register_udf("barcode", barcode, [pa.utf8()], pa.utf8(), "immutable")
+
+    fastq_path = DATA / "test.fastq.gz"
+    result = session.sql(
+        f"""
+        SELECT barcode(sequence) AS barcode, sequence
+        FROM fastq_scan('{fastq_path}')
+        """
+    ).to_arrow()
+
+    for barcode, sequence in zip(
+        result.column("barcode").to_pylist(), result.column("sequence").to_pylist()
+    ):
+        assert barcode == sequence[:4]
+
+
+def test_register_udf_errors():
+    """Test errors raised by a Python scalar UDF."""
+    import pyarrow as pa
+
+    session = new_session()
+
+    with pytest.raises(ValueError):
+        session.register_udf("f", lambda x: x, [pa.int64()], pa.int64(), "sometimes")
+
+    def wrong_type(x: pa.Array) -> pa.Array:
+        return pa.array(["a"] * len(x))
+
+    session.register_udf("wrong_type", wrong_type, [pa.int64()], pa.int64())
+
+    with pytest.raises(Exception):
+        session.sql("SELECT wrong_type(1)").to_arrow()
+
+
+def test_register_udaf():
+    """Test registering a Python aggregate UDF."""
+    import pyarrow as pa
+    import pyarrow.compute as pc
+
+    class MeanLength:
+        def __init__(self):
+            self._total = 0
+            self._count = 0
+
+        def update(self, sequence: pa.Array) -> None:
+            lengths = pc.utf8_length(sequence)
+            self._total += pc.sum(lengths).as_py() or 0
+            self._count += len(sequence) - sequence.null_count
+
+        def merge(self, totals: pa.Array, counts: pa.Array) -> None:
+            self._total += pc.sum(totals).as_py() or 0
+            self._count += pc.sum(counts).as_py() or 0
+
+        def state(self) -> list:
+            return [self._total, self._count]
+
+        def evaluate(self) -> float:
+            return self._total / self._count if self._count else None
+
+    session = new_session()
+    session.register_udaf(
+        "mean_length",
+        MeanLength,
+        [pa.utf8()],
+        pa.float64(),
+        [pa.int64(), pa.int64()],
+        "immutable",
+    )
+
+    result = session.sql(
+        """
+        SELECT grp, mean_length(s) AS mean_length
+        FROM (
+            SELECT 'a' grp, 'AC' s
+            UNION ALL
+            SELECT 'a' grp, 'ACGT' s
+            UNION ALL
+            SELECT 'b' grp, 'A' s
+        ) t
+        GROUP BY grp
+        ORDER BY grp
+        """
+    ).to_arrow()
+
+    assert result.column("mean_length").to_pylist() == [3.0, 1.0]
+
+
+def test_register_udtf():
+    """Test registering a Python table function."""
+    import pyarrow as pa
+
+    def lims_scan(path: str, n: int):
+        schema = pa.schema([("path", pa.utf8()), ("i", pa.int64())])
+        for i in range(n):
+            yield pa.record_batch([pa.array([path]), pa.array([i])], schema=schema)
+
+    session = new_session()
+    session.register_udtf("lims_scan", lims_scan)
+
+    result = session.sql(
+        "SELECT path, SUM(i) AS total FROM lims_scan('export.csv', 4) GROUP BY path"
+    ).to_arrow()
+
+    assert result.column("path").to_pylist() == ["export.csv"]
+    assert result.column("total").to_pylist() == [6]
+
+    # Each query calls the function again, so the table can be scanned repeatedly
+    assert len(session.sql("SELECT * FROM lims_scan('export.csv', 2)").to_arrow()) == 2
+
+
+def test_register_udtf_reader():
+    """Test a Python table function that returns a RecordBatchReader."""
+    import pyarrow as pa
+
+    def reader_scan(value: str):
+        table = pa.table({"value": [value, value]})
+        return pa.RecordBatchReader.from_batches(table.schema, table.to_batches())
+
+    session = new_session()
+    session.register_udtf("reader_scan", reader_scan)
+
+    result = session.sql("SELECT * FROM reader_scan('x')").to_arrow()
+
+    assert result.column("value").to_pylist() == ["x", "x"]
+
+    with pytest.raises(Exception):
+        session.sql("SELECT * FROM reader_scan(value)")
+
+
+def test_sql_with_params():
+    """Test binding positional and named parameters to a query."""
+    session = new_session()
+
+    gff_path = DATA / "test.gff"
+    session.execute(f"CREATE EXTERNAL TABLE gff_file STORED AS GFF LOCATION '{gff_path}'")
+
+    result = session.sql(
+        "SELECT * FROM gff_file WHERE seqname = $1 AND start >= $2", params=["sq0", 0]
+    ).to_arrow()
+    assert len(result) == 1
+
+    result = session.sql(
+        "SELECT * FROM gff_file WHERE seqname = $name", params={"name": "it's not here"}
+    ).to_arrow()
+    assert len(result) == 0
+
+    with pytest.raises(TypeError):
+        session.sql("SELECT * FROM gff_file WHERE seqname = $1", params="sq0")
+
+
+def test_prepare():
+    """Test executing a prepared query with different parameters."""
+    import pyarrow as pa
+
+    session = new_session()
+
+    gff_path = DATA / "test.gff"
+    session.execute(f"CREATE EXTERNAL TABLE gff_file STORED AS GFF LOCATION '{gff_path}'")
+
+    query = session.prepare("SELECT * FROM gff_file WHERE seqname = $1")
+    assert query.parameter_types() == {"$1": pa.utf8()}
+
+    assert len(query.execute(["sq0"]).to_arrow()) == 1
+    assert len(query.execute(["sq1"]).to_arrow()) == 1
+    assert len(query.execute(["missing"]).to_arrow()) == 0
+
+
+def test_sql_async():
+    """Test planning, collecting and executing queries without blocking the event loop."""
+    session = new_session()
+    gff_path = DATA / "test.gff"
+
+    async def run():
+        await session.execute_async(
+            f"CREATE EXTERNAL TABLE gff_file STORED AS GFF LOCATION '{gff_path}'"
+        )
+
+        results = await asyncio.gather(
+            session.sql_async("SELECT * FROM gff_file WHERE seqname = $1", ["sq0"]),
+            session.sql_async("SELECT * FROM gff_file"),
+        )
+
+        return [await result.collect_async() for result in results]
+
+    filtered, everything = asyncio.run(run())
+
+    assert sum(len(batch) for batch in filtered) == 1
+    assert sum(len(batch) for batch in everything) == 2
+
+
+def test_sql_async_error():
+    """Test that planning errors are raised from the awaitable."""
+    session = new_session()
+
+    async def run():
+        await session.sql_async("SELECT * FROM missing_table")
+
+    with pytest.raises(PlanningError, match="not found"):
+        asyncio.run(run())
+
+
+def test_async_batch_iteration():
+    """Test iterating over the batches of a result with async for."""
+    import pyarrow as pa
+
+    session = new_session(SessionConfig(batch_size=1))
+    fasta_path = DATA / "test.fasta"
+
+    async def run():
+        result = await session.sql_async(f"SELECT * FROM fasta_scan('{fasta_path}')")
+        return [batch async for batch in result]
+
+    batches = asyncio.run(run())
+
+    assert len(batches) > 1
+    assert all(isinstance(batch, pa.RecordBatch) for batch in batches)
+    assert sum(len(batch) for batch in batches) == 2
+
+
+def slow_batches():
+    """Yield single-row batches forever, slowly enough that a query never finishes."""
+    import pyarrow as pa
+
+    while True:
+        time.sleep(0.01)
+        yield pa.record_batch({"x": [1]})
+
+
+def test_cancel_from_another_thread():
+    """Test that cancel() interrupts a collect running in another thread."""
+    session = new_session()
+    session.register_table("slow", slow_batches())
+
+    result = session.sql("SELECT count(*) FROM slow")
+    threading.Timer(0.5, result.cancel).start()
+
+    with pytest.raises(concurrent.futures.CancelledError):
+        result.to_arrow()
+
+
+def test_cancel_collect_async():
+    """Test that cancel() stops an in-flight collect_async."""
+    session = new_session()
+    session.register_table("slow", slow_batches())
+
+    result = session.sql("SELECT count(*) FROM slow")
+
+    async def run():
+        asyncio.get_running_loop().call_later(0.5, result.cancel)
+        await result.collect_async()
+
+    with pytest.raises(concurrent.futures.CancelledError):
+        asyncio.run(run())
+
+
+@pytest.mark.skipif(sys.platform == "win32", reason="requires SIGINT")
+def test_keyboard_interrupt_cancels_query():
+    """Test that Ctrl-C interrupts a running query with KeyboardInterrupt."""
+    session = new_session()
+    session.register_table("slow", slow_batches())
+
+    threading.Timer(0.5, os.kill, (os.getpid(), signal.SIGINT)).start()
+
+    with pytest.raises(KeyboardInterrupt):
+        session.execute("SELECT count(*) FROM slow")
+
+
+def test_progress_callback():
+    """Test that a progress callback is called with the scan's final progress."""
     session = connect()
 
-    query = "SELECT * FROM fasta_scan('s3://test-bucket/test.fasta')"
-    arrow_table = session.sql(query).to_arrow()
+    fasta_path = (DATA / "test.fasta").as_posix()
 
-    assert len(arrow_table) == 2
+    reports = []
+    df = session.read_fasta_file(fasta_path).with_progress(reports.append).to_polars()
+
+    assert len(df) == 2
 
+    final = reports[-1]
+    assert final.finished
+    assert final.rows == 2
+    assert final.files_opened == 1
+    assert final.bytes_read > 0
+    assert final.partitions_completed == final.partitions_total
 
-def test_copy_to_s3():
-    """Test copying to s3."""
+
+def test_read_file_progress_callback():
+    """Test that the read_*_file methods take a progress callback."""
     session = connect()
+    fasta_path = (DATA / "test.fasta").as_posix()
 
-    s3_input_path = "s3://test-bucket/test.fasta"
-    parquet_output = "s3://parquet-bucket/test.parquet"
+    reports = []
+    result = session.read_fasta_file(fasta_path, progress=reports.append)
 
-    query = f"COPY (SELECT * FROM fasta_scan('{s3_input_path}')) TO '{parquet_output}'"
+    batches = list(result.iter_batches())
+    assert sum(len(batch) for batch in batches) == 2
 
-    session.register_object_store_from_url(parquet_output)
+    final = reports[-1]
+    assert final.finished
+    assert final.rows == 2
 
-    # Should not raise an exception
-    session.execute(query)
+
+def test_async_progress_callback():
+    """Test that collect_async and async for report the scan's progress."""
+    session = connect()
+    fasta_path = (DATA / "test.fasta").as_posix()
+
+    collect_reports = []
+    stream_reports = []
+
+    async def run():
+        result = session.read_fasta_file(fasta_path)
+
+        batches = await result.with_progress(collect_reports.append).collect_async()
+        assert sum(len(batch) for batch in batches) == 2
+
+        batches = [batch async for batch in result.with_progress(stream_reports.append)]
+        assert sum(len(batch) for batch in batches) == 2
+
+    asyncio.run(run())
+
+    for reports in (collect_reports, stream_reports):
+        final = reports[-1]
+        assert final.finished
+        assert final.rows == 2
+        assert [report.finished for report in reports].count(True) == 1
+
+
+def test_batch_iteration_progress_callback():
+    """Test that iterating over batches and reading the Arrow stream report progress."""
+    import pyarrow as pa
+
+    session = connect()
+    fasta_path = (DATA / "test.fasta").as_posix()
+    result = session.read_fasta_file(fasta_path)
+
+    iter_reports = []
+    batches = list(result.with_progress(iter_reports.append).iter_batches(max_rows=1))
+    assert sum(len(batch) for batch in batches) == 2
+
+    reader_reports = []
+    reader = result.with_progress(reader_reports.append).to_arrow_record_batch_reader()
+    assert sum(len(batch) for batch in reader) == 2
+
+    stream_reports = []
+    capsule = result.with_progress(stream_reports.append).__arrow_c_stream__()
+    pa.RecordBatchReader._import_from_c_capsule(capsule).read_all()
+
+    partition_reports = []
+    partitions = result.with_progress(partition_reports.append).iter_partitions()
+    assert sum(len(batch) for partition in partitions for batch in partition) == 2
+
+    for reports in (iter_reports, reader_reports, stream_reports, partition_reports):
+        final = reports[-1]
+        assert final.finished
+        assert final.rows == 2
+        assert [report.finished for report in reports].count(True) == 1
+
+
+def test_tqdm_progress():
+    """Test that TqdmProgress renders a bar over the scan partitions."""
+    pytest.importorskip("tqdm")
+
+    session = connect()
+    fasta_path = (DATA / "test.fasta").as_posix()
+
+    df = session.read_fasta_file(fasta_path).with_progress(TqdmProgress()).to_polars()
+
+    assert len(df) == 2
+
+
+def test_progress_callback_error_stops_query():
+    """Test that an exception raised by the progress callback stops the query."""
+    session = new_session()
+    session.register_table("slow", slow_batches())
+
+    def callback(progress):
+        raise RuntimeError("stop")
+
+    with pytest.raises(RuntimeError, match="stop"):
+        session.sql("SELECT count(*) FROM slow").with_progress(callback).collect()
+
+
+def test_explain():
+    """Test that explain describes the plans without executing the query."""
+    session = connect()
+
+    gff_path = (DATA / "test.gff").as_posix()
+    result = session.sql(f"SELECT * FROM gff_scan('{gff_path}') WHERE seqname = 'sq0'")
+
+    plan = result.explain()
+
+    assert "Filter" in plan.logical_plan
+    assert "GFFScan" in plan.physical_plan
+    assert plan.metrics is None
+    assert "Physical Plan" in str(plan)
+
+
+def test_explain_analyze():
+    """Test that explain with analyze reports the metrics of each operator."""
+    session = connect()
+
+    gff_path = (DATA / "test.gff").as_posix()
+    result = session.sql(f"SELECT * FROM gff_scan('{gff_path}') WHERE seqname = 'sq0'")
+
+    plan = result.explain(analyze=True)
+
+    assert "output_rows" in plan.physical_plan
+    assert plan.metrics[0].depth == 0
+    assert plan.metrics[-1].operator == "GFFScan"
+    assert plan.files_opened == 1
+    assert plan.bytes_read > 0
+
+    filter_metrics = next(m for m in plan.metrics if m.operator == "FilterExec")
+    assert filter_metrics.output_rows == 1
+    assert "Metrics" in str(plan)
+
+    scan_metrics = plan.metrics[-1]
+    assert scan_metrics.bytes_scanned == plan.bytes_read
+    assert scan_metrics.files_pruned == 0
+
+
+def test_explain_analyze_partition_pruning(tmp_path):
+    """Test that explain with analyze reports the files a scan's partition filter pruned."""
+    for part in ["1", "2"]:
+        (tmp_path / f"part={part}").mkdir()
+        shutil.copy(DATA / "test.gff", tmp_path / f"part={part}" / "test.gff")
+
+    session = connect()
+    session.execute(
+        "CREATE EXTERNAL TABLE parts STORED AS GFF PARTITIONED BY (part) "
+        f"LOCATION '{tmp_path.as_posix()}/'"
+    )
+
+    plan = session.sql("SELECT * FROM parts WHERE part = '1'").explain(analyze=True)
+
+    scan_metrics = next(m for m in plan.metrics if m.operator == "GFFScan")
+    assert scan_metrics.files_pruned == 1
+    assert scan_metrics.bytes_scanned > 0
+    assert plan.files_opened == 1
+
+
+def test_arrow_c_stream():
+    """Test that Polars reads a result through the Arrow PyCapsule interface."""
+    session = connect()
+
+    gff_path = (DATA / "test.gff").as_posix()
+    result = session.sql(f"SELECT seqname, start FROM gff_scan('{gff_path}')")
+
+    df = pl.DataFrame(result)
+
+    assert df.columns == ["seqname", "start"]
+    assert len(df) == 2
+
+
+def test_arrow_c_stream_requested_schema():
+    """Test that the stream is cast to a requested schema, and ignores one it can't satisfy."""
+    import pyarrow as pa
+
+    session = connect()
+    result = session.sql("SELECT * FROM (VALUES (1, 'a'), (2, 'b')) AS t(x, y)")
+
+    reader = pa.RecordBatchReader.from_stream(result, schema=pa.schema([("x", pa.float64())]))
+    assert reader.read_all().to_pydict() == {"x": [1.0, 2.0]}
+
+    schema = pa.schema([("z", pa.int64())])
+    capsule = result.__arrow_c_stream__(schema.__arrow_c_schema__())
+    reader = pa.RecordBatchReader._import_from_c_capsule(capsule)
+    assert reader.schema.names == ["x", "y"]
+
+
+def test_arrow_c_schema():
+    """Test that the schema is exported through the Arrow PyCapsule interface."""
+    import pyarrow as pa
+
+    session = connect()
+    result = session.sql("SELECT 1 AS x, 'a' AS y")
+
+    assert pa.schema(result) == result.schema()
+
+
+def test_iterate_batches():
+    """Test iterating over a result's batches with for."""
+    session = connect()
+
+    gff_path = (DATA / "test.gff").as_posix()
+    result = session.sql(f"SELECT * FROM gff_scan('{gff_path}')")
+
+    batches = list(result)
+
+    assert sum(batch.num_rows for batch in batches) == 2
+    assert batches[0].schema.names == result.schema().names
+
+
+def test_iter_batches_max_rows():
+    """Test that iter_batches splits batches larger than max_rows."""
+    session = connect()
+    result = session.sql("SELECT * FROM unnest(range(10)) AS t(x)")
+
+    sizes = [batch.num_rows for batch in result.iter_batches(max_rows=3)]
+
+    assert sizes == [3, 3, 3, 1]
+
+    with pytest.raises(ValueError, match="max_rows"):
+        result.iter_batches(max_rows=0)
+
+
+def test_iter_partitions():
+    """Test consuming each output partition in its own thread."""
+    session = new_session(SessionConfig(target_partitions=3))
+    result = session.sql(
+        "SELECT x % 7 AS k, count(*) AS n FROM unnest(range(100)) AS t(x) GROUP BY k"
+    )
+
+    partitions = result.iter_partitions()
+
+    with concurrent.futures.ThreadPoolExecutor(len(partitions)) as executor:
+        counts = executor.map(lambda batches: sum(b.num_rows for b in batches), partitions)
+
+    assert len(partitions) == 3
+    assert sum(counts) == 7
+
+
+def test_filter_select_sort():
+    """Test chaining SQL expressions onto a result."""
+    session = connect()
+
+    gff_path = (DATA / "test.gff").as_posix()
+    result = session.read_gff_file(gff_path)
+
+    df = (
+        result.filter("start >= 0")
+        .with_column("length", '"end" - start')
+        .select("seqname", "length", "start + 1 AS Next")
+        .sort("seqname DESC")
+        .limit(1)
+        .to_polars()
+    )
+
+    assert df.columns == ["seqname", "length", "next"]
+    assert len(df) == 1
+
+
+def test_join_and_aggregate():
+    """Test joining results and aggregating with SQL expressions."""
+    session = connect()
+
+    gff_path = (DATA / "test.gff").as_posix()
+    features = session.read_gff_file(gff_path)
+    samples = session.sql(
+        "SELECT * FROM (VALUES ('sq0', 's1'), ('sq1', 's2')) AS t(name, sample)"
+    )
+
+    df = (
+        features.join(samples, on="seqname = name", how="left")
+        .aggregate(["sample"], ["count(*) AS n"])
+        .sort("sample")
+        .to_polars()
+    )
+
+    assert df.columns == ["sample", "n"]
+    assert df["n"].sum() == 2
+
+    left = session.sql("SELECT * FROM (VALUES ('a'), ('b')) AS t(name)")
+    right = session.sql("SELECT * FROM (VALUES ('a')) AS u(name)")
+
+    assert left.join(right, on=["name"], how="anti").to_polars()["name"].to_list() == ["b"]
+
+
+def test_union_and_distinct():
+    """Test combining results and removing duplicates."""
+    session = connect()
+
+    gff_path = (DATA / "test.gff").as_posix()
+    result = session.read_gff_file(gff_path).select("seqname", "start")
+
+    assert len(result.union(result).to_polars()) == 4
+    assert len(result.union(result, distinct=True).to_polars()) == 2
+    assert len(result.union(result).distinct().to_polars()) == 2
+
+
+def test_chaining_errors():
+    """Test that invalid expressions raise planning errors."""
+    session = connect()
+
+    gff_path = (DATA / "test.gff").as_posix()
+    result = session.read_gff_file(gff_path)
+
+    with pytest.raises(PlanningError, match="nope"):
+        result.filter("nope > 1")
+
+    with pytest.raises(SQLSyntaxError):
+        result.select("start +")
+
+    with pytest.raises(ValueError, match="join type"):
+        result.join(result, on=["seqname"], how="sideways")
+
+
+def test_to_pandas():
+    """Test converting a result to a pandas DataFrame."""
+    pytest.importorskip("pandas")
+
+    session = connect()
+
+    gff_path = (DATA / "test.gff").as_posix()
+    df = session.read_gff_file(gff_path).to_pandas()
+
+    assert len(df) == 2
+    assert "seqname" in df.columns
+
+
+def test_head_and_show(capsys):
+    """Test previewing the first rows of a result."""
+    session = connect()
+    result = session.sql("SELECT * FROM unnest(range(100)) AS t(x)")
+
+    assert len(result.head(3).to_polars()) == 3
+    assert "Limit: skip=0, fetch=3" in result.head(3).explain().logical_plan
+
+    result.show(2)
+
+    assert capsys.readouterr().out.splitlines() == [
+        "+---+",
+        "| x |",
+        "+---+",
+        "| 0 |",
+        "| 1 |",
+        "+---+",
+    ]
+
+
+def test_notebook_display():
+    """Test the result's repr and its HTML rendering for notebooks."""
+    session = connect()
+
+    gff_path = (DATA / "test.gff").as_posix()
+    result = session.read_gff_file(gff_path).select("seqname", "attributes")
+
+    assert repr(result) == (
+        "ExecutionResult(seqname: Utf8, attributes: Map<Utf8, List<Utf8>>)"
+    )
+
+    html = result._repr_html_()
+    assert "<th>seqname<br><small>Utf8</small></th>" in html
+    assert "<td>sq0</td>" in html
+    assert "Showing the first" not in html
+
+    html = session.sql("SELECT * FROM unnest(range(100)) AS t(x)")._repr_html_()
+    assert html.count("<tr><td>") == 10
+    assert "Showing the first 10 rows" in html
+
+
+def test_count():
+    """Test counting the rows of a result."""
+    session = connect()
+
+    gff_path = (DATA / "test.gff").as_posix()
+    result = session.read_gff_file(gff_path)
+
+    assert result.count() == 2
+    assert result.filter("seqname = 'sq0'").count() == 1
+
+
+def test_describe():
+    """Test summarizing each column of a result."""
+    session = connect()
+    result = session.sql(
+        "SELECT * FROM (VALUES (1, 'a'), (NULL, 'a'), (3, NULL)) AS t(x, s)"
+    )
+
+    summary = result.describe()
+
+    assert summary.schema().names == [
+        "column",
+        "data_type",
+        "count",
+        "null_count",
+        "min",
+        "max",
+        "mean",
+        "distinct_count",
+    ]
+
+    rows = summary.to_polars().rows(named=True)
+    assert rows[0] == {
+        "column": "x",
+        "data_type": "Int64",
+        "count": 2,
+        "null_count": 1,
+        "min": "1",
+        "max": "3",
+        "mean": 2.0,
+        "distinct_count": None,
+    }
+    assert rows[1]["mean"] is None
+    assert rows[1]["distinct_count"] == 1
+
+
+def test_describe_no_columns():
+    """Test that summarizing a result without columns gives a summary without rows."""
+    session = connect()
+
+    summary = session.sql("SELECT 1 AS x").select().describe()
+
+    assert summary.schema().names == [
+        "column",
+        "data_type",
+        "count",
+        "null_count",
+        "min",
+        "max",
+        "mean",
+        "distinct_count",
+    ]
+    assert summary.count() == 0
+
+
+def test_describe_scans_once():
+    """Test that describe computes every column's statistics in one scan."""
+    session = connect()
+
+    gff_path = (DATA / "test.gff").as_posix()
+    plan = session.read_gff_file(gff_path).describe().explain()
+
+    assert plan.physical_plan.count("GFFScan") == 1
+
+
+def test_write_files(tmp_path):
+    """Test writing a result to each file format and reading it back."""
+    session = connect()
+
+    gff_path = (DATA / "test.gff").as_posix()
+    result = session.read_gff_file(gff_path).select("seqname", "start", '"end"')
+
+    assert result.write_parquet(str(tmp_path / "gff.parquet"), compression="zstd(3)") == 2
+    assert result.write_csv(str(tmp_path / "gff.csv.gz"), compression="gzip") == 2
+    assert result.write_json(str(tmp_path / "gff.json")) == 2
+    assert result.write_ipc(str(tmp_path / "gff.arrow")) == 2
+
+    lines = (tmp_path / "gff.json").read_text().splitlines()
+    assert lines[0] == '{"seqname":"sq0","start":8,"end":13}'
+
+    session.execute(
+        f"CREATE EXTERNAL TABLE gff_parquet STORED AS PARQUET LOCATION '{tmp_path / 'gff.parquet'}'"
+    )
+    assert session.sql("SELECT * FROM gff_parquet").count() == 2
+
+    session.execute(
+        f"CREATE EXTERNAL TABLE gff_csv STORED AS CSV LOCATION '{tmp_path / 'gff.csv.gz'}' "
+        "OPTIONS ('format.compression' 'gzip', 'format.has_header' 'true')"
+    )
+    assert session.sql("SELECT * FROM gff_csv").count() == 2
+
+
+def test_write_partitioned(tmp_path):
+    """Test writing a result partitioned hive-style by a column."""
+    session = connect()
+
+    gff_path = (DATA / "test.gff").as_posix()
+    result = session.read_gff_file(gff_path).select("seqname", "start", '"end"')
+
+    written = result.write_parquet(
+        f"{tmp_path}/parts/", row_group_size=1, partition_by=["seqname"]
+    )
+
+    assert written == 2
+    assert sorted(p.name for p in (tmp_path / "parts").iterdir()) == [
+        "seqname=sq0",
+        "seqname=sq1",
+    ]
+
+    session.execute(
+        "CREATE EXTERNAL TABLE parts STORED AS PARQUET PARTITIONED BY (seqname) "
+        f"LOCATION '{tmp_path}/parts/'"
+    )
+    assert session.sql("SELECT * FROM parts WHERE seqname = 'sq1'").count() == 1
+
+
+def test_write_invalid_compression(tmp_path):
+    """Test an invalid compression codec is rejected before anything is written."""
+    session = connect()
+
+    gff_path = (DATA / "test.gff").as_posix()
+    result = session.read_gff_file(gff_path)
+
+    with pytest.raises(ValueError, match="compression"):
+        result.write_parquet(str(tmp_path / "gff.parquet"), compression="nope")
+
+    with pytest.raises(ValueError):
+        result.write_csv(str(tmp_path / "gff.csv"), compression="nope")
+
+    assert not list(tmp_path.iterdir())
+
+
+def test_plan_returns_schema():
+    """Test that plan resolves the output schema without executing the query."""
+    session = connect()
+
+    gff_path = (DATA / "test.gff").as_posix()
+    session.execute(f"CREATE EXTERNAL TABLE gff STORED AS GFF LOCATION '{gff_path}'")
+
+    schema = session.plan("SELECT seqname, start + 1 AS next FROM gff WHERE start > $1", [1])
+
+    assert schema.names == ["seqname", "next"]
+
+
+def test_plan_does_not_run_ddl():
+    """Test that planning a DDL statement doesn't create the table."""
+    session = connect()
+
+    gff_path = (DATA / "test.gff").as_posix()
+    session.plan(f"CREATE EXTERNAL TABLE gff STORED AS GFF LOCATION '{gff_path}'")
+
+    with pytest.raises(PlanningError, match="not found"):
+        session.plan("SELECT * FROM gff")
+
+
+def test_plan_syntax_error_position():
+    """Test that syntax errors are raised with the position of the unexpected token."""
+    session = connect()
+
+    with pytest.raises(SQLSyntaxError) as excinfo:
+        session.plan("SELECT 1\nFORM t")
+
+    assert excinfo.value.line == 2
+    assert excinfo.value.column == 6
+    assert isinstance(excinfo.value, ValueError)
+
+
+def test_sql_planning_errors():
+    """Test that planning a query raises the same errors as plan does."""
+    session = connect()
+
+    for method in (session.sql, session.execute, session.prepare):
+        with pytest.raises(PlanningError, match="not found"):
+            method("SELECT * FROM missing_table")
+
+    with pytest.raises(SQLSyntaxError) as excinfo:
+        session.sql("SELECT (1\nFROM t")
+
+    assert excinfo.value.line == 2
+    assert excinfo.value.column == 1
+
+
+def test_validate():
+    """Test that validate returns planning errors instead of raising them."""
+    session = connect()
+
+    assert session.validate("SELECT 1") is None
+
+    error = session.validate("SELECT missing FROM (SELECT 1 AS present)")
+
+    assert isinstance(error, PlanningError)
+    assert not isinstance(error, SQLSyntaxError)
+    assert "missing" in str(error)
+    assert error.line is None
+
+
+def test_validate_exon_statement(tmp_path):
+    """Test that validate and plan accept the Exon statements sql does, without running them."""
+    session = connect()
+
+    fasta_path = (DATA / "test.fasta").as_posix()
+    session.execute(
+        f"CREATE EXTERNAL TABLE fasta_file STORED AS FASTA LOCATION '{fasta_path}'"
+    )
+
+    output_path = tmp_path / "copy.fasta"
+    query = f"COPY fasta_file TO '{output_path.as_posix()}' STORED AS FASTA"
+
+    assert session.validate(query) is None
+    assert session.plan(query) == session.sql(query).schema()
+    assert not output_path.exists()
+
+    invalid = "COPY fasta_file STORED AS FASTA"
+
+    assert isinstance(session.validate(invalid), SQLSyntaxError)
+    with pytest.raises(SQLSyntaxError):
+        session.sql(invalid)
+
+
+def test_execute_script():
+    """Test running a script of DDL and queries from a file."""
+    session = connect()
+
+    gff_path = (DATA / "test.gff").as_posix()
+
+    with tempfile.TemporaryDirectory() as tmpdir:
+        script_path = Path(tmpdir) / "script.sql"
+        script_path.write_text(
+            f"""
+            -- Register the GFF file and count its features by sequence.
+            CREATE EXTERNAL TABLE gff STORED AS GFF LOCATION '{gff_path}';
+
+            CREATE TABLE counts AS SELECT seqname, count(*) AS n FROM gff GROUP BY seqname;
+
+            SELECT * FROM counts ORDER BY seqname;
+            """
+        )
+
+        df = session.execute_script(script_path).to_polars()
+
+    assert df["seqname"].to_list() == ["sq0", "sq1"]
+
+
+def test_execute_script_text():
+    """Test running a script given as text, where only the last statement is returned."""
+    session = connect()
+
+    result = session.execute_script(
+        "CREATE TABLE t AS VALUES (1), (2); INSERT INTO t VALUES (3); SELECT count(*) AS n FROM t"
+    )
+
+    assert result.to_polars()["n"].to_list() == [3]
+
+
+def test_execute_script_str_is_sql(tmp_path):
+    """Test that a str is run as SQL text, even if it's the path of a file."""
+    session = connect()
+
+    script_path = tmp_path / "script.sql"
+    script_path.write_text("SELECT 1 AS n")
+
+    with pytest.raises(SQLSyntaxError):
+        session.execute_script(str(script_path))
+
+    assert session.execute_script(script_path).to_polars()["n"].to_list() == [1]
+
+
+def test_execute_script_reports_failed_statement():
+    """Test that the failing statement is identified in the error."""
+    session = connect()
+
+    with pytest.raises(PlanningError, match="Statement 2 of 3") as excinfo:
+        session.execute_script("SELECT 1;\nSELECT missing FROM (SELECT 1 AS present);\nSELECT 2")
+
+    assert excinfo.value.statement_index == 1
+    assert excinfo.value.statement == "SELECT missing FROM (SELECT 1 AS present)"
+
+
+def test_execute_script_syntax_error():
+    """Test that syntax errors are reported at their position in the script."""
+    session = connect()
+
+    with pytest.raises(SQLSyntaxError) as excinfo:
+        session.execute_script("SELECT 1;\n  SELECT 2 FORM t")
+
+    assert excinfo.value.line == 2
+    assert excinfo.value.column == 17
+
+
+def test_session_set_and_get():
+    """Test changing DataFusion and Exon options on a running session."""
+    session = connect()
+
+    session.set("datafusion.execution.batch_size", 1024)
+    session.set("exon.vcf_parse_info", True)
+
+    assert session.get("datafusion.execution.batch_size") == "1024"
+    assert session.get("exon.vcf_parse_info") == "true"
+
+    plan = session.sql("SELECT * FROM (VALUES (1), (2)) AS t(x) WHERE x > 1").explain()
+    assert "target_batch_size=1024" in plan.physical_plan
+
+    settings = session.settings()
+    assert settings["datafusion.execution.batch_size"] == "1024"
+    assert "exon.vcf_parse_formats" in settings
+
+
+def test_session_set_invalid():
+    """Test that invalid options and values raise ValueError."""
+    session = connect()
+
+    with pytest.raises(ValueError):
+        session.set("datafusion.execution.batch_size", "many")
+
+    with pytest.raises(ValueError):
+        session.set("datafusion.not_an_option", 1)
+
+    with pytest.raises(ValueError, match="Unknown configuration option"):
+        session.get("datafusion.not_an_option")
+
+
+RUNTIME_THREADS_SCRIPT = """
+import os
+import biobear
+
+if os.environ.get("CONFIGURE"):
+    biobear.configure_runtime(worker_threads=3)
+
+session = biobear.connect()
+session.execute("SELECT 1")
+
+threads = [
+    open(f"/proc/self/task/{task}/comm").read().strip()
+    for task in os.listdir("/proc/self/task")
+]
+print(sum(name.startswith("biobear-python") for name in threads))
+"""
+
+
+@pytest.mark.skipif(sys.platform != "linux", reason="Counts threads with /proc")
+@pytest.mark.parametrize(
+    "env,expected",
+    [({"CONFIGURE": "1"}, 3), ({"BIOBEAR_WORKER_THREADS": "2"}, 2)],
+)
+def test_configure_runtime_threads(env, expected):
+    """Test that the runtime's worker threads come from configure_runtime or the environment."""
+    import subprocess
+
+    result = subprocess.run(
+        [sys.executable, "-c", RUNTIME_THREADS_SCRIPT],
+        env={**os.environ, **env},
+        capture_output=True,
+        text=True,
+        check=True,
+    )
+
+    assert int(result.stdout) == expected
+
+
+def test_configure_runtime_after_start():
+    """Test that the runtime can't be configured once it's running."""
+    session = connect()
+    session.execute("SELECT 1")
+
+    with pytest.raises(ValueError, match="already started"):
+        configure_runtime(worker_threads=2)
+
+
+def _count_fasta_records(path):
+    session = connect()
+    return len(session.read_fasta_file(path).to_polars())
+
+
+@pytest.mark.skipif(not hasattr(os, "fork"), reason="Requires os.fork")
+def test_query_in_forked_workers():
+    """Test that forked processes can run queries after the parent started the runtime."""
+    import multiprocessing
+
+    fasta_path = (DATA / "test.fasta").as_posix()
+    expected = _count_fasta_records(fasta_path)
+
+    with multiprocessing.get_context("fork").Pool(2) as pool:
+        counts = pool.map_async(_count_fasta_records, [fasta_path] * 4).get(timeout=60)
+
+    assert counts == [expected] * 4
+
+
+def test_read_from_s3():
+    """Test reading from s3."""
+    session = connect()
+
+    query = "SELECT * FROM fasta_scan('s3://test-bucket/test.fasta')"
+    arrow_table = session.sql(query).to_arrow()
+
+    assert len(arrow_table) == 2
+
+
+def test_copy_to_s3():
+    """Test copying to s3."""
+    session = connect()
+
+    s3_input_path = "s3://test-bucket/test.fasta"
+    parquet_output = "s3://parquet-bucket/test.parquet"
+
+    query = f"COPY (SELECT * FROM fasta_scan('{s3_input_path}')) TO '{parquet_output}'"
+
+    session.register_object_store_from_url(parquet_output)
+
+    # Should not raise an exception
+    session.execute(query)
+
+
+def test_register_s3():
+    """Test registering an S3 bucket with explicit credentials and endpoint."""
+    session = connect()
+
+    session.register_s3(
+        "test-bucket",
+        endpoint="http://localhost:4566",
+        region="us-east-1",
+        access_key="test",
+        secret_key="test",
+        allow_http=True,
+    )
+
+    query = "SELECT * FROM fasta_scan('s3://test-bucket/test.fasta')"
+    arrow_table = session.sql(query).to_arrow()
+
+    assert len(arrow_table) == 2
+
+
+def test_register_object_store_invalid_bucket():
+    """Test that an invalid bucket name raises a ValueError."""
+    session = connect()
+
+    with pytest.raises(ValueError):
+        session.register_s3("not a bucket")
 
 
 def test_read_bcf_file():
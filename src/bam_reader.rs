@@ -15,23 +15,22 @@
 use arrow::ffi_stream::ArrowArrayStreamReader;
 use arrow::ffi_stream::FFI_ArrowArrayStream;
 use arrow::pyarrow::IntoPyArrow;
+use datafusion::common::{ParamValues, ScalarValue};
 use exon::ffi::DataFrameRecordBatchStream;
 use exon::new_exon_config;
 use exon::ExonSession;
 use pyo3::prelude::*;
 
-use tokio::runtime::Runtime;
-
 use std::io;
-use std::sync::Arc;
 
 use crate::error::BioBearError;
+use crate::params::bind_param_values;
+use crate::runtime::get_tokio_runtime;
 
 #[pyclass(name = "_BamIndexedReader")]
 pub struct BamIndexedReader {
     path: String,
     batch_size: Option<usize>,
-    _runtime: Arc<Runtime>,
 }
 
 impl BamIndexedReader {
@@ -44,12 +43,9 @@ impl BamIndexedReader {
             ));
         }
 
-        let rt = Arc::new(Runtime::new()?);
-
         Ok(Self {
             path: path.to_string(),
             batch_size,
-            _runtime: rt,
         })
     }
 }
@@ -74,31 +70,31 @@ impl BamIndexedReader {
 
         let ctx = ExonSession::with_config_exon(config).map_err(BioBearError::from)?;
 
-        let df = self._runtime.block_on(async {
+        let runtime = get_tokio_runtime()?;
+
+        let df = runtime.block_on(async {
+            // LOCATION doesn't accept placeholders, so escape any quotes in the path instead.
             ctx.sql(&format!(
                 "CREATE EXTERNAL TABLE bam_file STORED AS INDEXED_BAM LOCATION '{}'",
-                self.path.as_str()
+                self.path.replace('\'', "''")
             ))
             .await?;
 
-            let sql = format!(
-                "SELECT * FROM bam_file WHERE bam_region_filter('{}', reference) = true",
-                region
-            );
-
-            match ctx.sql(&sql).await {
-                Ok(df) => Ok(df),
-                Err(e) => Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    format!("Error reading BAM file: {e}"),
-                )),
+            let sql = "SELECT * FROM bam_file WHERE bam_region_filter($1, reference) = true";
+            let params = ParamValues::List(vec![ScalarValue::from(region)]);
+
+            match ctx.sql(sql).await {
+                Ok(df) => bind_param_values(df, params),
+                Err(e) => Err(BioBearError::IOError(format!(
+                    "Error reading BAM file: {e}"
+                ))),
             }
         })?;
 
-        let mut stream_ptr = self._runtime.block_on(async {
+        let mut stream_ptr = runtime.block_on(async {
             let stream = df.execute_stream().await?;
             let dataset_record_batch_stream =
-                DataFrameRecordBatchStream::new(stream, self._runtime.clone());
+                DataFrameRecordBatchStream::new(stream, runtime.clone());
 
             Ok::<FFI_ArrowArrayStream, BioBearError>(FFI_ArrowArrayStream::new(Box::new(
                 dataset_record_batch_stream,
@@ -20,19 +20,16 @@ use exon::ffi::DataFrameRecordBatchStream;
 use exon::ExonSession;
 use noodles::core::Region;
 use pyo3::prelude::*;
-use tokio::runtime::Runtime;
-
 use std::io;
 use std::str::FromStr;
-use std::sync::Arc;
 
 use crate::error::BioBearError;
+use crate::runtime::get_tokio_runtime;
 
 #[pyclass(name = "_BCFIndexedReader")]
 pub struct BCFIndexedReader {
     path: String,
     batch_size: Option<usize>,
-    _runtime: Arc<Runtime>,
 }
 
 impl BCFIndexedReader {
@@ -45,12 +42,9 @@ impl BCFIndexedReader {
             ));
         }
 
-        let rt = Arc::new(Runtime::new()?);
-
         Ok(Self {
             path: path.to_string(),
             batch_size,
-            _runtime: rt,
         })
     }
 }
@@ -71,26 +65,24 @@ impl BCFIndexedReader {
 
         let ctx = ExonSession::with_config_exon(config).map_err(BioBearError::from)?;
 
-        let region = Region::from_str(region).map_err(|e| {
-            io::Error::new(io::ErrorKind::Other, format!("Error parsing region: {e}"))
-        })?;
+        let region = Region::from_str(region)
+            .map_err(|e| io::Error::other(format!("Error parsing region: {e}")))?;
 
         let options = ListingBCFTableOptions::default().with_regions(vec![region]);
 
-        let df = self._runtime.block_on(async {
+        let runtime = get_tokio_runtime()?;
+
+        let df = runtime.block_on(async {
             match ctx.read_bcf(self.path.as_str(), options).await {
                 Ok(df) => Ok(df),
-                Err(e) => Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    format!("Error reading BCF file: {e}"),
-                )),
+                Err(e) => Err(io::Error::other(format!("Error reading BCF file: {e}"))),
             }
         })?;
 
-        let mut stream_ptr = self._runtime.block_on(async {
+        let mut stream_ptr = runtime.block_on(async {
             let stream = df.execute_stream().await?;
             let dataset_record_batch_stream =
-                DataFrameRecordBatchStream::new(stream, self._runtime.clone());
+                DataFrameRecordBatchStream::new(stream, runtime.clone());
 
             Ok::<_, BioBearError>(FFI_ArrowArrayStream::new(Box::new(
                 dataset_record_batch_stream,
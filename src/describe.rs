@@ -0,0 +1,179 @@
+// Copyright 2024 WHERE TRUE Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use arrow::datatypes::{DataType, Field, Schema};
+use datafusion::{
+    common::{Column, DFSchema, ScalarValue},
+    error::Result,
+    functions::core::expr_fn::{get_field, named_struct},
+    functions_aggregate::expr_fn::{approx_distinct, avg, count, max, min},
+    functions_nested::expr_fn::make_array,
+    logical_expr::{cast, col, lit, EmptyRelation, Expr, LogicalPlan},
+    prelude::DataFrame,
+};
+
+use crate::display::format_type;
+
+/// The column holding each column's statistics while they're unnested into rows.
+const STATS: &str = "__biobear_stats";
+
+/// The column holding the number of rows.
+const ROWS: &str = "__biobear_rows";
+
+/// The statistics describing each column, in the order they're output.
+const STATISTICS: [&str; 8] = [
+    "column",
+    "data_type",
+    "count",
+    "null_count",
+    "min",
+    "max",
+    "mean",
+    "distinct_count",
+];
+
+/// Build a plan summarizing each of the DataFrame's columns in a row, with its non-null and null
+/// counts, its min and max as strings, its mean if it's numeric, and an estimate of its distinct
+/// values if it's a string.
+///
+/// The statistics are computed in a single aggregation, so the input is only scanned once. A
+/// DataFrame without columns is summarized without rows.
+pub(crate) fn describe(df: DataFrame) -> Result<DataFrame> {
+    if df.schema().fields().is_empty() {
+        return empty_summary(df);
+    }
+
+    let mut aggregates = vec![count(lit(1)).alias(ROWS)];
+
+    let stats = df
+        .schema()
+        .iter()
+        .enumerate()
+        .map(|(i, (qualifier, field))| {
+            let column = Expr::Column(Column::from((qualifier, field)));
+            column_stats(i, field.name(), field.data_type(), column, &mut aggregates)
+        })
+        .collect::<Vec<_>>();
+
+    let output = STATISTICS
+        .iter()
+        .map(|name| get_field(col(STATS), *name).alias(*name))
+        .collect::<Vec<_>>();
+
+    df.aggregate(vec![], aggregates)?
+        .select(vec![make_array(stats).alias(STATS)])?
+        .unnest_columns(&[STATS])?
+        .select(output)
+}
+
+/// Build a summary without rows, as there are no columns to aggregate.
+fn empty_summary(df: DataFrame) -> Result<DataFrame> {
+    let types = [
+        DataType::Utf8,
+        DataType::Utf8,
+        DataType::Int64,
+        DataType::Int64,
+        DataType::Utf8,
+        DataType::Utf8,
+        DataType::Float64,
+        DataType::Int64,
+    ];
+
+    let fields = STATISTICS
+        .iter()
+        .zip(types)
+        .map(|(name, data_type)| Field::new(*name, data_type, true))
+        .collect::<Vec<_>>();
+
+    let (state, _) = df.into_parts();
+    let plan = LogicalPlan::EmptyRelation(EmptyRelation {
+        produce_one_row: false,
+        schema: Arc::new(DFSchema::try_from(Schema::new(fields))?),
+    });
+
+    Ok(DataFrame::new(state, plan))
+}
+
+/// Add the aggregates computing the statistics of the `i`th column, and return the struct of
+/// its statistics built from them.
+///
+/// The structs have the same fields and types for every column, so they can be collected into a
+/// list and unnested into rows.
+fn column_stats(
+    i: usize,
+    name: &str,
+    data_type: &DataType,
+    column: Expr,
+    aggregates: &mut Vec<Expr>,
+) -> Expr {
+    let is_string = matches!(
+        data_type,
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View
+    );
+    let is_orderable = data_type.is_numeric()
+        || data_type.is_temporal()
+        || is_string
+        || matches!(data_type, DataType::Boolean);
+
+    let mut aggregate = |statistic: &str, expr: Expr| {
+        let alias = format!("__biobear_{statistic}_{i}");
+        aggregates.push(expr.alias(&alias));
+        col(alias)
+    };
+
+    let non_null = aggregate("count", count(column.clone()));
+
+    let (min, max) = if is_orderable {
+        (
+            cast(aggregate("min", min(column.clone())), DataType::Utf8),
+            cast(aggregate("max", max(column.clone())), DataType::Utf8),
+        )
+    } else {
+        (lit(ScalarValue::Utf8(None)), lit(ScalarValue::Utf8(None)))
+    };
+
+    let mean = if data_type.is_numeric() {
+        cast(aggregate("mean", avg(column.clone())), DataType::Float64)
+    } else {
+        lit(ScalarValue::Float64(None))
+    };
+
+    let distinct_count = if is_string {
+        let distinct = approx_distinct(cast(column, DataType::Utf8));
+        cast(aggregate("distinct", distinct), DataType::Int64)
+    } else {
+        lit(ScalarValue::Int64(None))
+    };
+
+    let values = [
+        lit(name),
+        lit(format_type(data_type)),
+        non_null.clone(),
+        col(ROWS) - non_null,
+        min,
+        max,
+        mean,
+        distinct_count,
+    ];
+
+    named_struct(
+        STATISTICS
+            .iter()
+            .zip(values)
+            .flat_map(|(name, value)| [lit(*name), value])
+            .collect(),
+    )
+}
@@ -0,0 +1,178 @@
+// Copyright 2024 WHERE TRUE Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt::Write;
+
+use arrow::{
+    array::RecordBatch,
+    datatypes::{DataType, Schema},
+    util::display::{ArrayFormatter, FormatOptions},
+};
+
+use crate::error::BioBearResult;
+
+/// Render the schema and the first rows of a result as an HTML table, for notebooks.
+///
+/// The header gives each column's name and type. `truncated` notes that the result has more
+/// rows than those shown.
+pub(crate) fn format_html(
+    schema: &Schema,
+    batches: &[RecordBatch],
+    truncated: bool,
+) -> BioBearResult<String> {
+    let options = FormatOptions::default().with_null("null");
+    let mut html = String::from("<table>\n<thead>\n<tr>");
+
+    for field in schema.fields() {
+        write!(
+            html,
+            "<th>{}<br><small>{}</small></th>",
+            escape(field.name()),
+            escape(&format_type(field.data_type()))
+        )
+        .expect("writing to a String can't fail");
+    }
+
+    html.push_str("</tr>\n</thead>\n<tbody>\n");
+
+    let mut rows = 0;
+    for batch in batches {
+        let formatters = batch
+            .columns()
+            .iter()
+            .map(|column| ArrayFormatter::try_new(column.as_ref(), &options))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for row in 0..batch.num_rows() {
+            html.push_str("<tr>");
+            for formatter in &formatters {
+                write!(
+                    html,
+                    "<td>{}</td>",
+                    escape(&formatter.value(row).to_string())
+                )
+                .expect("writing to a String can't fail");
+            }
+            html.push_str("</tr>\n");
+        }
+
+        rows += batch.num_rows();
+    }
+
+    html.push_str("</tbody>\n</table>\n");
+
+    if truncated {
+        writeln!(html, "<p>Showing the first {rows} rows</p>")
+            .expect("writing to a String can't fail");
+    }
+
+    Ok(html)
+}
+
+/// Format a data type compactly, e.g. `Map<Utf8, List<Utf8>>` rather than with every detail
+/// of the nested fields.
+pub(crate) fn format_type(data_type: &DataType) -> String {
+    match data_type {
+        DataType::List(field) | DataType::LargeList(field) | DataType::ListView(field) => {
+            format!("List<{}>", format_type(field.data_type()))
+        }
+        DataType::FixedSizeList(field, size) => {
+            format!("FixedSizeList<{}, {size}>", format_type(field.data_type()))
+        }
+        DataType::Struct(fields) => {
+            let fields = fields
+                .iter()
+                .map(|field| format!("{}: {}", field.name(), format_type(field.data_type())))
+                .collect::<Vec<_>>();
+
+            format!("Struct<{}>", fields.join(", "))
+        }
+        DataType::Map(field, _) => match field.data_type() {
+            DataType::Struct(entries) if entries.len() == 2 => format!(
+                "Map<{}, {}>",
+                format_type(entries[0].data_type()),
+                format_type(entries[1].data_type())
+            ),
+            entries => format!("Map<{}>", format_type(entries)),
+        },
+        DataType::Dictionary(key, value) => {
+            format!("Dictionary<{}, {}>", format_type(key), format_type(value))
+        }
+        data_type => data_type.to_string(),
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::{
+        array::{ArrayRef, Int64Array, StringArray},
+        datatypes::{Field, Fields},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_format_html() -> BioBearResult<()> {
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "name",
+                Arc::new(StringArray::from(vec![Some("<a>"), None])) as ArrayRef,
+            ),
+            ("pos", Arc::new(Int64Array::from(vec![1, 2])) as ArrayRef),
+        ])?;
+
+        let html = format_html(&batch.schema(), &[batch], true)?;
+
+        assert!(html.contains("<th>name<br><small>Utf8</small></th>"));
+        assert!(html.contains("<tr><td>&lt;a&gt;</td><td>1</td></tr>"));
+        assert!(html.contains("<tr><td>null</td><td>2</td></tr>"));
+        assert!(html.contains("Showing the first 2 rows"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_type() {
+        let values = DataType::List(Arc::new(Field::new("item", DataType::Utf8, true)));
+        let entries = Fields::from(vec![
+            Field::new("keys", DataType::Utf8, false),
+            Field::new("values", values, true),
+        ]);
+        let map = DataType::Map(
+            Arc::new(Field::new("entries", DataType::Struct(entries), false)),
+            false,
+        );
+
+        assert_eq!(format_type(&map), "Map<Utf8, List<Utf8>>");
+    }
+}
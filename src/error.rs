@@ -15,7 +15,21 @@
 use arrow::error::ArrowError;
 use datafusion::{error::DataFusionError, sql::sqlparser::parser::ParserError};
 use exon::ExonError;
-use pyo3::PyErr;
+use pyo3::{create_exception, exceptions::PyValueError, prelude::*, PyTypeInfo};
+
+create_exception!(
+    biobear,
+    PlanningError,
+    PyValueError,
+    "Raised when a SQL query can't be planned, e.g. it references an unknown table or column."
+);
+
+create_exception!(
+    biobear,
+    SQLSyntaxError,
+    PlanningError,
+    "Raised when a SQL query can't be parsed."
+);
 
 #[derive(Debug)]
 pub enum BioBearError {
@@ -23,12 +37,73 @@ pub enum BioBearError {
     Other(String),
     InvalidCompressionType(String),
     ParserError(String),
+    InvalidConfiguration(String),
+    PlanningError(String),
+    SQLSyntaxError(String),
 }
 
 impl BioBearError {
     pub fn new(msg: &str) -> Self {
         Self::Other(msg.to_string())
     }
+
+    /// Convert an error from planning a query, distinguishing syntax and planning errors from
+    /// e.g. IO errors while inferring a table's schema.
+    pub fn planning(value: DataFusionError) -> Self {
+        match value.find_root() {
+            DataFusionError::SQL(..) => Self::SQLSyntaxError(value.to_string()),
+            DataFusionError::Plan(_)
+            | DataFusionError::SchemaError(..)
+            | DataFusionError::NotImplemented(_) => Self::PlanningError(value.to_string()),
+            _ => Self::from(value),
+        }
+    }
+}
+
+/// Find the line and column the SQL parser reported an error at, e.g. `at Line: 1, Column: 8`.
+fn error_position(msg: &str) -> Option<(u64, u64)> {
+    let position = &msg[msg.rfind("Line: ")? + "Line: ".len()..];
+    let (line, position) = position.split_once(", Column: ")?;
+
+    let column_end = position
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(position.len());
+
+    Some((line.parse().ok()?, position[..column_end].parse().ok()?))
+}
+
+/// Create an exception of type `T` with `line` and `column` attributes, which are `None` if the
+/// message doesn't include a position.
+fn positioned_error<T: PyTypeInfo>(msg: String) -> PyErr {
+    let (line, column) = error_position(&msg).unzip();
+
+    Python::with_gil(|py| {
+        let err = PyErr::new::<T, _>(msg);
+
+        let value = err.value_bound(py);
+        if let Err(e) = value
+            .setattr("line", line)
+            .and_then(|_| value.setattr("column", column))
+        {
+            return e;
+        }
+
+        err
+    })
+}
+
+impl std::fmt::Display for BioBearError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BioBearError::IOError(msg)
+            | BioBearError::Other(msg)
+            | BioBearError::InvalidCompressionType(msg)
+            | BioBearError::ParserError(msg)
+            | BioBearError::InvalidConfiguration(msg)
+            | BioBearError::PlanningError(msg)
+            | BioBearError::SQLSyntaxError(msg) => write!(f, "{msg}"),
+        }
+    }
 }
 
 impl From<BioBearError> for PyErr {
@@ -40,6 +115,11 @@ impl From<BioBearError> for PyErr {
                 PyErr::new::<pyo3::exceptions::PyValueError, _>(msg)
             }
             BioBearError::ParserError(msg) => PyErr::new::<pyo3::exceptions::PyValueError, _>(msg),
+            BioBearError::InvalidConfiguration(msg) => {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(msg)
+            }
+            BioBearError::PlanningError(msg) => positioned_error::<PlanningError>(msg),
+            BioBearError::SQLSyntaxError(msg) => positioned_error::<SQLSyntaxError>(msg),
         }
     }
 }
@@ -82,3 +162,16 @@ impl From<std::io::Error> for BioBearError {
 }
 
 pub type BioBearResult<T> = std::result::Result<T, BioBearError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_position() {
+        let msg = "SQL error: ParserError(\"Expected: end of statement, found: FORM at Line: 2, Column: 13\")";
+
+        assert_eq!(error_position(msg), Some((2, 13)));
+        assert_eq!(error_position("Schema error: No field named x."), None);
+    }
+}
@@ -12,36 +12,213 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::sync::Arc;
+use std::{
+    future::Future,
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
 
 use arrow::{
-    datatypes::Schema,
+    array::RecordBatch,
+    compute::concat_batches,
+    datatypes::{Schema, SchemaRef},
     ffi_stream::{ArrowArrayStreamReader, FFI_ArrowArrayStream},
     pyarrow::{IntoPyArrow, PyArrowType, ToPyArrow},
+    util::pretty::pretty_format_batches,
+};
+use datafusion::{
+    common::JoinType,
+    error::DataFusionError,
+    execution::SendableRecordBatchStream,
+    physical_plan::{
+        collect as common_collect, common, execute_stream, execute_stream_partitioned,
+    },
+    prelude::DataFrame,
 };
-use datafusion::prelude::DataFrame;
-use exon::ffi::DataFrameRecordBatchStream;
 use pyo3::{
+    exceptions::{PyImportError, PyValueError},
     pyclass, pymethods,
-    types::{PyAnyMethods, PyTuple},
-    IntoPy, PyObject, PyResult, Python, ToPyObject,
+    types::{PyAnyMethods, PyCapsule, PyString, PyStringMethods, PyTuple},
+    Bound, IntoPy, PyAny, PyErr, PyObject, PyRef, PyResult, Python, ToPyObject,
 };
-use tokio::runtime::Runtime;
+use tokio_util::sync::CancellationToken;
 
 use crate::{
-    error::{self, BioBearError},
-    runtime::wait_for_future,
+    describe::describe,
+    display::{format_html, format_type},
+    error::BioBearError,
+    explain::QueryPlan,
+    progress::{
+        create_physical_plan, report_stream, CountedScanExec, ProgressHook, ProgressReceiver,
+        ProgressRecordBatchReader, ScanCounters, SharedReporter,
+    },
+    pycapsule::{schema_to_capsule, stream_to_capsule},
+    record_batch_stream::{RecordBatchIterator, RecordBatchStream},
+    runtime::{
+        cancellable_future_into_py, get_tokio_runtime, wait_for_cancellable_future,
+        wait_for_cancellable_future_with_ticks, wait_for_future,
+    },
+    sql_expr::SqlExprParser,
+    write::{rows_written, write_plan, WriteFormat, WriteOptions},
 };
 
+/// The number of rows shown when a result is displayed in a notebook.
+const HTML_ROWS: usize = 10;
+
+/// The interval between progress reports unless `with_progress` is given another.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
 #[pyclass(name = "ExecutionResult", subclass)]
 #[derive(Clone)]
 pub(crate) struct ExecutionResult {
     pub(super) df: Arc<DataFrame>,
+    cancellation: Arc<Mutex<CancellationToken>>,
+    progress: Option<Arc<ProgressHook>>,
 }
 
 impl ExecutionResult {
     pub(crate) fn new(df: DataFrame) -> Self {
-        Self { df: Arc::new(df) }
+        Self {
+            df: Arc::new(df),
+            cancellation: Arc::new(Mutex::new(CancellationToken::new())),
+            progress: None,
+        }
+    }
+
+    /// Create a result that reports its progress to `callback`, if given, as with
+    /// `with_progress(callback)`.
+    pub(crate) fn with_progress_callback(df: DataFrame, callback: Option<PyObject>) -> Self {
+        Self {
+            progress: callback.map(|callback| {
+                Arc::new(ProgressHook {
+                    callback,
+                    interval: PROGRESS_INTERVAL,
+                })
+            }),
+            ..Self::new(df)
+        }
+    }
+
+    /// Get the token that cancels operations started now, when `cancel` is called.
+    fn cancellation_token(&self) -> CancellationToken {
+        self.cancellation
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Create a result for a query derived from this one, which reports its progress the same way.
+    fn derive(&self, df: Result<DataFrame, DataFusionError>) -> PyResult<Self> {
+        let df = df.map_err(BioBearError::planning)?;
+
+        Ok(Self {
+            progress: self.progress.clone(),
+            ..Self::new(df)
+        })
+    }
+
+    /// Wait for the future with the GIL released, until it completes, Ctrl-C is pressed or the
+    /// result is cancelled.
+    fn wait_for<F, T>(&self, py: Python, f: F) -> PyResult<T>
+    where
+        F: Future<Output = Result<T, DataFusionError>> + Send + 'static,
+        T: Send + 'static,
+    {
+        wait_for_cancellable_future(py, f, &self.cancellation_token())
+    }
+
+    /// Execute the plan and collect its batches along with the physical schema.
+    fn collect_with_schema(&self, py: Python) -> PyResult<(SchemaRef, Vec<RecordBatch>)> {
+        let reporter = SharedReporter::new(1);
+
+        let result = wait_for_cancellable_future_with_ticks(
+            py,
+            collect_plan(
+                self.df.as_ref().clone(),
+                self.progress.clone(),
+                reporter.clone(),
+            ),
+            &self.cancellation_token(),
+            &mut |py| reporter.report(py),
+        )?;
+
+        reporter.report(py)?;
+
+        Ok(result)
+    }
+
+    /// Execute the query as a single stream, along with the receiver of the progress it reports
+    /// as its batches are read.
+    fn execute_stream_with_progress(
+        &self,
+        py: Python,
+    ) -> PyResult<(SendableRecordBatchStream, Option<ProgressReceiver>)> {
+        let df = self.df.as_ref().clone();
+        let progress = self.progress.clone();
+
+        let (stream, reporter) = self.wait_for(py, async move {
+            let (plan, task_ctx, reporter) = create_physical_plan(df, progress).await?;
+
+            Ok((execute_stream(plan, task_ctx)?, reporter))
+        })?;
+
+        Ok(match reporter {
+            Some(reporter) => {
+                let (stream, receiver) = report_stream(stream, reporter);
+                (stream, Some(receiver))
+            }
+            None => (stream, None),
+        })
+    }
+
+    /// Execute the query for at most `n` rows, which lets e.g. scans stop early, and collect
+    /// them into a single batch.
+    fn collect_head(&self, py: Python, n: usize) -> PyResult<RecordBatch> {
+        let df = self
+            .df
+            .as_ref()
+            .clone()
+            .limit(0, Some(n))
+            .map_err(BioBearError::planning)?;
+
+        let (schema, batches) = self.wait_for(py, async move {
+            let stream = df.execute_stream().await?;
+            let schema = stream.schema();
+            let batches = common::collect(stream).await?;
+
+            Ok((schema, batches))
+        })?;
+
+        Ok(concat_batches(&schema, &batches).map_err(BioBearError::from)?)
+    }
+
+    /// Collect the batches into a pyarrow Table with the physical schema.
+    fn collect_table<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let (schema, batches) = self.collect_with_schema(py)?;
+
+        let batches = batches
+            .into_iter()
+            .map(|rb| rb.to_pyarrow(py))
+            .collect::<PyResult<Vec<_>>>()?;
+        let schema = schema.to_pyarrow(py)?;
+
+        let table_class = py.import_bound("pyarrow")?.getattr("Table")?;
+        table_class.call_method1("from_batches", (batches, schema))
+    }
+
+    /// Execute the query, streaming its batches to files at the path, and return the number of
+    /// rows written.
+    fn write(
+        &self,
+        py: Python,
+        path: &str,
+        format: WriteFormat,
+        options: WriteOptions,
+    ) -> PyResult<usize> {
+        let df = write_plan(self.df.as_ref().clone(), path, format, options)?;
+        let (_, batches) = self.derive(Ok(df))?.collect_with_schema(py)?;
+
+        Ok(rows_written(&batches))
     }
 }
 
@@ -49,11 +226,373 @@ impl ExecutionResult {
 impl ExecutionResult {
     /// Collect the batches and return a list of pyarrow RecordBatch
     fn collect(&self, py: Python) -> PyResult<Vec<PyObject>> {
-        let batches = wait_for_future(py, self.df.as_ref().clone().collect())
-            .map_err(error::BioBearError::from)?;
+        let (_, batches) = self.collect_with_schema(py)?;
         batches.into_iter().map(|rb| rb.to_pyarrow(py)).collect()
     }
 
+    /// Collect the batches without blocking the event loop, returning an awaitable list of
+    /// pyarrow RecordBatch
+    fn collect_async(&self, py: Python) -> PyResult<PyObject> {
+        let df = self.df.as_ref().clone();
+        let reporter = SharedReporter::new(1);
+        let tick_reporter = reporter.clone();
+
+        cancellable_future_into_py(
+            py,
+            collect_plan(df, self.progress.clone(), reporter.clone()),
+            &self.cancellation_token(),
+            move |py| tick_reporter.report(py),
+            move |py, (_, batches)| {
+                reporter.report(py)?;
+
+                let batches = batches
+                    .into_iter()
+                    .map(|rb| rb.to_pyarrow(py))
+                    .collect::<PyResult<Vec<_>>>()?;
+                Ok(batches.into_py(py))
+            },
+        )
+    }
+
+    /// Return a copy of this result that calls `callback` with a `Progress` at most every
+    /// `interval` seconds while it's collected, and once more when it finishes
+    ///
+    /// Applies to the methods that collect the result, e.g. `to_polars()`, `to_arrow()` or
+    /// `collect_async()`, to iterating over its batches, e.g. with `iter_batches()` or
+    /// `async for`, and to the streams exported through the Arrow PyCapsule interface and
+    /// `to_arrow_record_batch_reader()`, which report as their batches are read. The `read_*_file`
+    /// methods take the callback as their `progress` argument. An exception raised by the callback
+    /// stops the query.
+    #[pyo3(signature = (callback, interval=0.5))]
+    fn with_progress(&self, callback: PyObject, interval: f64) -> PyResult<Self> {
+        let interval = Duration::try_from_secs_f64(interval).map_err(|_| {
+            BioBearError::InvalidConfiguration(format!("Invalid progress interval: {interval}"))
+        })?;
+
+        Ok(Self {
+            progress: Some(Arc::new(ProgressHook { callback, interval })),
+            ..self.clone()
+        })
+    }
+
+    /// Describe how the query is planned, and with `analyze=True` execute it and include the
+    /// metrics recorded by each operator, e.g. the rows it produced and its compute time
+    ///
+    /// With `verbose=True` the plans include their schemas, and the analyzed plan includes all
+    /// metrics.
+    #[pyo3(signature = (analyze=false, verbose=false))]
+    fn explain(&self, py: Python, analyze: bool, verbose: bool) -> PyResult<QueryPlan> {
+        let counters = Arc::new(ScanCounters::default());
+        let (state, plan) = self.df.as_ref().clone().into_parts();
+        let state = counters.instrument(state);
+
+        let logical_plan = state.optimize(&plan).map_err(BioBearError::from)?;
+        let physical_plan = wait_for_future(py, state.create_physical_plan(&logical_plan))?;
+
+        let logical_plan = if verbose {
+            logical_plan.display_indent_schema().to_string()
+        } else {
+            logical_plan.display_indent().to_string()
+        };
+
+        if !analyze {
+            return Ok(QueryPlan::new(
+                logical_plan,
+                physical_plan.as_ref(),
+                verbose,
+            ));
+        }
+
+        let physical_plan =
+            CountedScanExec::wrap_scans(physical_plan).map_err(BioBearError::from)?;
+        self.wait_for(py, common_collect(physical_plan.clone(), state.task_ctx()))?;
+
+        Ok(QueryPlan::analyzed(
+            logical_plan,
+            &physical_plan,
+            verbose,
+            &counters,
+        ))
+    }
+
+    /// Cancel the operations currently running on this result, e.g. a `to_polars()` call in
+    /// another thread or an awaited `collect_async()`, which then raise
+    /// `concurrent.futures.CancelledError`
+    fn cancel(&self) {
+        let mut token = self
+            .cancellation
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        token.cancel();
+        *token = CancellationToken::new();
+    }
+
+    /// Return a result with the rows matching the SQL predicate, e.g. `"start > 100"`
+    fn filter(&self, predicate: &str) -> PyResult<Self> {
+        let predicate = SqlExprParser::new(&self.df).parse_expr(predicate)?;
+
+        self.derive(self.df.as_ref().clone().filter(predicate))
+    }
+
+    /// Return a result with the given SQL expressions as its columns, e.g.
+    /// `select("name", "end - start AS length")`
+    #[pyo3(signature = (*exprs))]
+    fn select(&self, exprs: Vec<String>) -> PyResult<Self> {
+        let parser = SqlExprParser::new(&self.df);
+        let exprs = exprs
+            .iter()
+            .map(|expr| parser.parse_expr(expr))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.derive(self.df.as_ref().clone().select(exprs))
+    }
+
+    /// Return a result with a column added, or replaced if it exists, from a SQL expression
+    fn with_column(&self, name: &str, expr: &str) -> PyResult<Self> {
+        let expr = SqlExprParser::new(&self.df).parse_expr(expr)?;
+
+        self.derive(self.df.as_ref().clone().with_column(name, expr))
+    }
+
+    /// Return a result sorted by the SQL sort expressions, e.g. `sort("chrom", "pos DESC")`
+    #[pyo3(signature = (*exprs))]
+    fn sort(&self, exprs: Vec<String>) -> PyResult<Self> {
+        let parser = SqlExprParser::new(&self.df);
+        let exprs = exprs
+            .iter()
+            .map(|expr| parser.parse_sort_expr(expr))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.derive(self.df.as_ref().clone().sort(exprs))
+    }
+
+    /// Return a result with at most `n` rows, after skipping `offset` rows
+    #[pyo3(signature = (n, offset=0))]
+    fn limit(&self, n: usize, offset: usize) -> PyResult<Self> {
+        self.derive(self.df.as_ref().clone().limit(offset, Some(n)))
+    }
+
+    /// Return a result joining this one with `right`
+    ///
+    /// `on` is either a SQL predicate over the columns of both sides, e.g. `"id = sample_id"`, or
+    /// a list of the columns both sides are joined on. `how` is one of `inner`, `left`, `right`,
+    /// `full`, `semi` or `anti`.
+    #[pyo3(signature = (right, on, how="inner"))]
+    fn join(&self, right: PyRef<'_, Self>, on: &Bound<'_, PyAny>, how: &str) -> PyResult<Self> {
+        let join_type = join_type(how)?;
+        let left = self.df.as_ref().clone();
+        let right = right.df.as_ref().clone();
+
+        if let Ok(predicate) = on.downcast::<PyString>() {
+            let schema = left
+                .schema()
+                .join(right.schema())
+                .map_err(BioBearError::planning)?;
+            let predicate = SqlExprParser::new(&left)
+                .with_schema(schema)
+                .parse_expr(predicate.to_str()?)?;
+
+            return self.derive(left.join_on(right, join_type, [predicate]));
+        }
+
+        let columns = on.extract::<Vec<String>>()?;
+        let columns = columns.iter().map(String::as_str).collect::<Vec<_>>();
+
+        self.derive(left.join(right, join_type, &columns, &columns, None))
+    }
+
+    /// Return a result grouping the rows by the `group_by` SQL expressions and computing the
+    /// `aggregates` for each group, e.g. `aggregate(["chrom"], ["count(*) AS n"])`
+    fn aggregate(&self, group_by: Vec<String>, aggregates: Vec<String>) -> PyResult<Self> {
+        let parser = SqlExprParser::new(&self.df);
+        let parse_all = |exprs: &[String]| {
+            exprs
+                .iter()
+                .map(|expr| parser.parse_expr(expr))
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        let group_by = parse_all(&group_by)?;
+        let aggregates = parse_all(&aggregates)?;
+
+        self.derive(self.df.as_ref().clone().aggregate(group_by, aggregates))
+    }
+
+    /// Return a result without duplicate rows
+    fn distinct(&self) -> PyResult<Self> {
+        self.derive(self.df.as_ref().clone().distinct())
+    }
+
+    /// Return a result with the rows of both results, whose columns must match, and with
+    /// `distinct=True` without duplicates
+    #[pyo3(signature = (other, distinct=false))]
+    fn union(&self, other: PyRef<'_, Self>, distinct: bool) -> PyResult<Self> {
+        let df = self.df.as_ref().clone();
+        let other = other.df.as_ref().clone();
+
+        if distinct {
+            self.derive(df.union_distinct(other))
+        } else {
+            self.derive(df.union(other))
+        }
+    }
+
+    /// Count the rows, using file statistics where available rather than reading the rows
+    fn count(&self, py: Python) -> PyResult<usize> {
+        self.wait_for(py, self.df.as_ref().clone().count())
+    }
+
+    /// Return a result summarizing each column in a row, with its non-null and null counts, its
+    /// min and max, its mean if it's numeric, and an estimate of its distinct values if it's a
+    /// string
+    ///
+    /// The statistics are computed by a single aggregation when the result is collected.
+    fn describe(&self) -> PyResult<Self> {
+        self.derive(describe(self.df.as_ref().clone()))
+    }
+
+    /// Return a result with the first `n` rows, which limits the plan so e.g. scans stop early
+    #[pyo3(signature = (n=5))]
+    fn head(&self, n: usize) -> PyResult<Self> {
+        self.derive(self.df.as_ref().clone().limit(0, Some(n)))
+    }
+
+    /// Print the first `n` rows as a table, executing the query only as far as needed
+    #[pyo3(signature = (n=20))]
+    fn show(&self, py: Python, n: usize) -> PyResult<()> {
+        let batch = self.collect_head(py, n)?;
+        let table = pretty_format_batches(&[batch]).map_err(BioBearError::from)?;
+
+        py.import_bound("builtins")?
+            .call_method1("print", (table.to_string(),))?;
+
+        Ok(())
+    }
+
+    /// Render the schema and the first rows as HTML in notebooks, executing the query only as
+    /// far as needed
+    fn _repr_html_(&self, py: Python) -> PyResult<String> {
+        // Fetch an extra row to tell whether there are more rows than those shown.
+        let batch = self.collect_head(py, HTML_ROWS + 1)?;
+        let truncated = batch.num_rows() > HTML_ROWS;
+        let batch = batch.slice(0, batch.num_rows().min(HTML_ROWS));
+
+        Ok(format_html(
+            self.df.schema().as_arrow(),
+            &[batch],
+            truncated,
+        )?)
+    }
+
+    fn __repr__(&self) -> String {
+        let columns = self
+            .df
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| format!("{}: {}", field.name(), format_type(field.data_type())))
+            .collect::<Vec<_>>();
+
+        format!("ExecutionResult({})", columns.join(", "))
+    }
+
+    /// Iterate over the pyarrow RecordBatches with `for`, executing the query as they're read
+    fn __iter__(&self) -> RecordBatchIterator {
+        RecordBatchIterator::new(
+            self.df.clone(),
+            self.progress.clone(),
+            None,
+            self.cancellation_token(),
+        )
+    }
+
+    /// Iterate over the pyarrow RecordBatches, splitting batches with more than `max_rows` rows
+    ///
+    /// The query executes as the batches are read, so a slow consumer holds back the scan
+    /// rather than the batches accumulating in memory.
+    #[pyo3(signature = (max_rows=None))]
+    fn iter_batches(&self, max_rows: Option<usize>) -> PyResult<RecordBatchIterator> {
+        validate_max_rows(max_rows)?;
+
+        Ok(RecordBatchIterator::new(
+            self.df.clone(),
+            self.progress.clone(),
+            max_rows,
+            self.cancellation_token(),
+        ))
+    }
+
+    /// Execute the query and return an iterator over the pyarrow RecordBatches of each of its
+    /// output partitions
+    ///
+    /// The iterators can be consumed from separate threads, as they release the GIL while they
+    /// wait for batches.
+    #[pyo3(signature = (max_rows=None))]
+    fn iter_partitions(
+        &self,
+        py: Python,
+        max_rows: Option<usize>,
+    ) -> PyResult<Vec<RecordBatchIterator>> {
+        validate_max_rows(max_rows)?;
+
+        let df = self.df.as_ref().clone();
+        let progress = self.progress.clone();
+
+        let (streams, progress_reporter) = self.wait_for(py, async move {
+            let (plan, task_ctx, reporter) = create_physical_plan(df, progress).await?;
+
+            Ok((execute_stream_partitioned(plan, task_ctx)?, reporter))
+        })?;
+
+        // The query is reported finished once every partition's iterator has finished.
+        let reporter = SharedReporter::new(streams.len());
+        reporter.set(progress_reporter);
+
+        Ok(streams
+            .into_iter()
+            .map(|stream| {
+                RecordBatchIterator::from_stream(
+                    self.df.clone(),
+                    stream,
+                    reporter.clone(),
+                    max_rows,
+                    self.cancellation_token(),
+                )
+            })
+            .collect())
+    }
+
+    /// Iterate over the pyarrow RecordBatches asynchronously with `async for`
+    fn __aiter__(&self) -> RecordBatchStream {
+        RecordBatchStream::new(
+            self.df.clone(),
+            self.progress.clone(),
+            self.cancellation_token(),
+        )
+    }
+
+    /// Export the schema through the Arrow PyCapsule interface
+    fn __arrow_c_schema__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyCapsule>> {
+        schema_to_capsule(py, self.df.schema().as_arrow())
+    }
+
+    /// Execute the query and export its batches through the Arrow PyCapsule interface, so e.g.
+    /// `polars.DataFrame(result)` reads them without pyarrow
+    ///
+    /// The batches are streamed as they're read. With `requested_schema` the columns are cast to
+    /// the requested types where possible, otherwise it's ignored.
+    #[pyo3(signature = (requested_schema=None))]
+    fn __arrow_c_stream__<'py>(
+        &self,
+        py: Python<'py>,
+        requested_schema: Option<Bound<'py, PyCapsule>>,
+    ) -> PyResult<Bound<'py, PyCapsule>> {
+        let (stream, progress) = self.execute_stream_with_progress(py)?;
+
+        stream_to_capsule(py, stream, progress, requested_schema.as_ref())
+    }
+
     /// Returns the schema from the logical plan
     ///
     /// Note: This is a logical schema and may not match the physical schema
@@ -78,14 +617,13 @@ impl ExecutionResult {
     #[allow(clippy::wrong_self_convention)]
     /// Convert to an Arrow RecordBatchReader
     fn to_arrow_record_batch_reader(&mut self, py: Python) -> PyResult<PyObject> {
-        let stream = wait_for_future(py, self.df.as_ref().clone().execute_stream())
-            .map_err(error::BioBearError::from)?;
+        let (stream, progress) = self.execute_stream_with_progress(py)?;
 
-        let runtime = Arc::new(Runtime::new()?);
+        let runtime = get_tokio_runtime()?;
 
-        let dataframe_record_batch_stream = DataFrameRecordBatchStream::new(stream, runtime);
+        let reader = ProgressRecordBatchReader::new(stream, runtime, progress);
 
-        let mut stream = FFI_ArrowArrayStream::new(Box::new(dataframe_record_batch_stream));
+        let mut stream = FFI_ArrowArrayStream::new(Box::new(reader));
 
         let stream_reader =
             unsafe { ArrowArrayStreamReader::from_raw(&mut stream).map_err(BioBearError::from) }?;
@@ -107,72 +645,189 @@ impl ExecutionResult {
         Ok(table)
     }
 
-    /// Convert to a Polars LazyFrame
-    fn _to_polars_lazy(&self, py: Python) -> PyResult<PyObject> {
-        let stream = wait_for_future(py, self.df.as_ref().clone().execute_stream())
-            .map_err(error::BioBearError::from)?;
-
-        let schema = stream.schema().to_pyarrow(py)?;
-
-        let runtime = Arc::new(Runtime::new()?);
-
-        let dataframe_record_batch_stream = DataFrameRecordBatchStream::new(stream, runtime);
+    /// Convert to a pandas DataFrame
+    fn to_pandas(&self, py: Python) -> PyResult<PyObject> {
+        let table = self.collect_table(py)?;
 
-        let mut stream = FFI_ArrowArrayStream::new(Box::new(dataframe_record_batch_stream));
-
-        let batches =
-            unsafe { ArrowArrayStreamReader::from_raw(&mut stream).map_err(BioBearError::from) }?;
-
-        let batches = batches.into_pyarrow(py)?;
+        Ok(table.call_method0("to_pandas")?.into())
+    }
 
-        let table_class = py.import_bound("pyarrow")?.getattr("Table")?;
-        let args = (batches, schema);
+    /// Convert to a Polars LazyFrame, which executes the query when it's collected
+    ///
+    /// Polars' projections, filters and row limits, e.g. from `head()`, are pushed down into
+    /// the query, and so to the scans, before any data is read. Polars before 1.0 has no IO
+    /// plugins, so there the result is collected and scanned as a pyarrow dataset instead.
+    fn _to_polars_lazy(&self, py: Python) -> PyResult<PyObject> {
+        let polars = py.import_bound("polars")?;
 
-        let table: PyObject = table_class.call_method1("from_batches", args)?.into();
+        let module = match py.import_bound("biobear.polars_source") {
+            Ok(module) => module,
+            Err(err) if err.is_instance_of::<PyImportError>(py) => {
+                let table = self.collect_table(py)?;
 
-        let dataset_class = py.import_bound("pyarrow.dataset")?;
+                let dataset_class = py.import_bound("pyarrow.dataset")?;
+                let dataset = dataset_class.call_method1("dataset", (table,))?;
 
-        let dataset: PyObject = dataset_class.call_method1("dataset", (table,))?.into();
+                return Ok(polars
+                    .call_method1("scan_pyarrow_dataset", (dataset,))?
+                    .into());
+            }
+            Err(err) => return Err(err),
+        };
 
-        let module = py.import_bound("polars")?;
-        let args = (dataset,);
-        let result = module.call_method1("scan_pyarrow_dataset", args)?.into();
+        let result = module
+            .call_method1("scan_execution_result", (self.clone(),))?
+            .into();
 
         Ok(result)
     }
 
-    /// Convert to a Polars DataFrame, if lazy=True, the DataFrame will be lazy
+    /// Convert to a Polars DataFrame, if lazy=True, the DataFrame will be lazy and executed
+    /// when it's collected
     #[pyo3(signature = (lazy=false))]
     fn to_polars(&self, py: Python, lazy: bool) -> PyResult<PyObject> {
         if lazy {
             return self._to_polars_lazy(py);
         }
 
-        let stream = wait_for_future(py, self.df.as_ref().clone().execute_stream())
-            .map_err(error::BioBearError::from)?;
+        let table = self.collect_table(py)?;
+
+        let module = py.import_bound("polars")?;
+        let args = (table,);
+        let result = module.call_method1("from_arrow", args)?.into();
+
+        Ok(result)
+    }
+
+    /// Write to Parquet at a local path or a URL of a registered object store, streaming the
+    /// batches as they're read, and return the number of rows written
+    ///
+    /// A path ending in `/` is written as a directory of files, as is one partitioned by the
+    /// `partition_by` columns, hive-style, e.g. `chrom=1/`. `compression` is a codec such as
+    /// `snappy` or `zstd(3)`, and `row_group_size` the maximum number of rows in a row group.
+    #[pyo3(signature = (path, *, compression=None, row_group_size=None, partition_by=None))]
+    fn write_parquet(
+        &self,
+        py: Python,
+        path: &str,
+        compression: Option<String>,
+        row_group_size: Option<usize>,
+        partition_by: Option<Vec<String>>,
+    ) -> PyResult<usize> {
+        let options = WriteOptions {
+            compression,
+            row_group_size,
+            partition_by: partition_by.unwrap_or_default(),
+        };
+
+        self.write(py, path, WriteFormat::Parquet, options)
+    }
 
-        let schema = stream.schema().to_pyarrow(py)?;
+    /// Write to CSV with a header, streaming the batches as they're read, and return the number
+    /// of rows written
+    ///
+    /// The path and `partition_by` are as for `write_parquet`. `compression` is a codec such as
+    /// `gzip` or `zstd`.
+    #[pyo3(signature = (path, *, compression=None, partition_by=None))]
+    fn write_csv(
+        &self,
+        py: Python,
+        path: &str,
+        compression: Option<String>,
+        partition_by: Option<Vec<String>>,
+    ) -> PyResult<usize> {
+        let options = WriteOptions {
+            compression,
+            partition_by: partition_by.unwrap_or_default(),
+            ..Default::default()
+        };
+
+        self.write(py, path, WriteFormat::Csv, options)
+    }
 
-        let runtime = Arc::new(Runtime::new()?);
+    /// Write to newline-delimited JSON, streaming the batches as they're read, and return the
+    /// number of rows written
+    ///
+    /// The path and `partition_by` are as for `write_parquet`. `compression` is a codec such as
+    /// `gzip` or `zstd`.
+    #[pyo3(signature = (path, *, compression=None, partition_by=None))]
+    fn write_json(
+        &self,
+        py: Python,
+        path: &str,
+        compression: Option<String>,
+        partition_by: Option<Vec<String>>,
+    ) -> PyResult<usize> {
+        let options = WriteOptions {
+            compression,
+            partition_by: partition_by.unwrap_or_default(),
+            ..Default::default()
+        };
+
+        self.write(py, path, WriteFormat::Json, options)
+    }
 
-        let dataframe_record_batch_stream = DataFrameRecordBatchStream::new(stream, runtime);
+    /// Write to Arrow IPC files, streaming the batches as they're read, and return the number of
+    /// rows written
+    ///
+    /// The path and `partition_by` are as for `write_parquet`. The files are compressed with
+    /// LZ4.
+    #[pyo3(signature = (path, *, partition_by=None))]
+    fn write_ipc(
+        &self,
+        py: Python,
+        path: &str,
+        partition_by: Option<Vec<String>>,
+    ) -> PyResult<usize> {
+        let options = WriteOptions {
+            partition_by: partition_by.unwrap_or_default(),
+            ..Default::default()
+        };
+
+        self.write(py, path, WriteFormat::Ipc, options)
+    }
+}
 
-        let mut stream = FFI_ArrowArrayStream::new(Box::new(dataframe_record_batch_stream));
+/// Plan and execute the query, collecting its batches along with the physical schema, and set the
+/// reporter of its progress on `reporter` once it's planned.
+async fn collect_plan(
+    df: DataFrame,
+    progress: Option<Arc<ProgressHook>>,
+    reporter: SharedReporter,
+) -> Result<(SchemaRef, Vec<RecordBatch>), DataFusionError> {
+    let (plan, task_ctx, progress_reporter) = create_physical_plan(df, progress).await?;
+    reporter.set(progress_reporter);
 
-        let batches =
-            unsafe { ArrowArrayStreamReader::from_raw(&mut stream).map_err(BioBearError::from) }?;
+    let stream = execute_stream(plan, task_ctx)?;
+    let schema = stream.schema();
+    let batches = common::collect(stream).await?;
 
-        let batches = batches.into_pyarrow(py)?;
+    reporter.finish_stream();
 
-        let table_class = py.import_bound("pyarrow")?.getattr("Table")?;
-        let args = (batches, schema);
+    Ok((schema, batches))
+}
 
-        let table: PyObject = table_class.call_method1("from_batches", args)?.into();
+fn validate_max_rows(max_rows: Option<usize>) -> Result<(), BioBearError> {
+    if max_rows == Some(0) {
+        return Err(BioBearError::InvalidConfiguration(
+            "max_rows must be greater than 0".to_string(),
+        ));
+    }
 
-        let module = py.import_bound("polars")?;
-        let args = (table,);
-        let result = module.call_method1("from_arrow", args)?.into();
+    Ok(())
+}
 
-        Ok(result)
+/// Parse the name of a join type, e.g. `left`.
+fn join_type(how: &str) -> PyResult<JoinType> {
+    match how.to_lowercase().as_str() {
+        "inner" => Ok(JoinType::Inner),
+        "left" => Ok(JoinType::Left),
+        "right" => Ok(JoinType::Right),
+        "full" | "outer" => Ok(JoinType::Full),
+        "semi" => Ok(JoinType::LeftSemi),
+        "anti" => Ok(JoinType::LeftAnti),
+        _ => Err(PyErr::new::<PyValueError, _>(format!(
+            "Invalid join type: {how}, expected one of inner, left, right, full, semi or anti"
+        ))),
     }
 }
@@ -13,7 +13,6 @@
 // limitations under the License.
 
 use std::str::FromStr;
-use std::sync::Arc;
 
 use arrow::ffi_stream::{ArrowArrayStreamReader, FFI_ArrowArrayStream};
 use arrow::pyarrow::IntoPyArrow;
@@ -22,15 +21,14 @@ use exon::datasources::ExonFileType;
 use exon::ffi::DataFrameRecordBatchStream;
 use exon::{new_exon_config, ExonRuntimeEnvExt, ExonSession};
 use pyo3::prelude::*;
-use tokio::runtime::Runtime;
 
 use crate::error::BioBearError;
+use crate::runtime::get_tokio_runtime;
 
 #[pyclass(name = "_ExonReader")]
 pub struct ExonReader {
     df: datafusion::dataframe::DataFrame,
     exhausted: bool,
-    _runtime: Arc<Runtime>,
 }
 
 impl ExonReader {
@@ -40,7 +38,7 @@ impl ExonReader {
         compression: Option<FileCompressionType>,
         batch_size: Option<usize>,
     ) -> Result<Self, BioBearError> {
-        let rt = Arc::new(Runtime::new()?);
+        let rt = get_tokio_runtime()?;
 
         let mut config = new_exon_config();
 
@@ -67,7 +65,6 @@ impl ExonReader {
 
         Ok(Self {
             df,
-            _runtime: rt,
             exhausted: false,
         })
     }
@@ -90,8 +87,7 @@ impl ExonReader {
             .transpose()
             .map_err(BioBearError::from)?;
 
-        let open = Self::open(path, exon_file_type, file_compression_type, batch_size)
-            .map_err(BioBearError::from)?;
+        let open = Self::open(path, exon_file_type, file_compression_type, batch_size)?;
 
         Ok(open)
     }
@@ -102,7 +98,9 @@ impl ExonReader {
 
     #[allow(clippy::wrong_self_convention)]
     fn to_pyarrow(&mut self) -> PyResult<PyObject> {
-        let mut stream_ptr = self._runtime.block_on(async {
+        let runtime = get_tokio_runtime()?;
+
+        let mut stream_ptr = runtime.block_on(async {
             let stream = self
                 .df
                 .clone()
@@ -111,7 +109,7 @@ impl ExonReader {
                 .map_err::<BioBearError, _>(|e| e.into())?;
 
             let dataset_record_batch_stream =
-                DataFrameRecordBatchStream::new(stream, self._runtime.clone());
+                DataFrameRecordBatchStream::new(stream, runtime.clone());
 
             Ok::<FFI_ArrowArrayStream, PyErr>(FFI_ArrowArrayStream::new(Box::new(
                 dataset_record_batch_stream,
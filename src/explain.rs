@@ -0,0 +1,265 @@
+// Copyright 2024 WHERE TRUE Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::HashMap, sync::Arc};
+
+use arrow::{
+    array::{ArrayRef, Float64Array, RecordBatch, StringArray, UInt64Array},
+    util::pretty::pretty_format_batches,
+};
+use datafusion::physical_plan::{display::DisplayableExecutionPlan, displayable, ExecutionPlan};
+use pyo3::prelude::*;
+
+use crate::{
+    error::BioBearResult,
+    progress::{CountedScanExec, ScanCounters, BYTES_SCANNED},
+};
+
+/// The metrics of an operator in an analyzed physical plan, summed over its partitions.
+#[pyclass(name = "OperatorMetrics", frozen, get_all)]
+#[derive(Debug, Clone)]
+pub(crate) struct OperatorMetrics {
+    /// The operator's name, e.g. `FilterExec`.
+    operator: String,
+    /// The operator as displayed in the plan, e.g. `FilterExec: chrom@0 = 1`.
+    description: String,
+    /// The operator's depth in the plan, the root being 0.
+    depth: usize,
+    /// The number of rows the operator produced, if it records it.
+    output_rows: Option<usize>,
+    /// The CPU time spent by the operator in seconds, if it records it.
+    elapsed_compute: Option<f64>,
+    /// The number of bytes the operator read, if it's a scan that read files.
+    bytes_scanned: Option<usize>,
+    /// The number of files listed for the tables the operator scanned that it didn't read, e.g.
+    /// as their partitions were pruned, if it's a scan of listed tables.
+    files_pruned: Option<usize>,
+    /// All the operator's metrics by name, with times in nanoseconds.
+    metrics: HashMap<String, usize>,
+}
+
+impl OperatorMetrics {
+    fn new(plan: &dyn ExecutionPlan, depth: usize, files_pruned: Option<usize>) -> Self {
+        let metrics = plan
+            .metrics()
+            .map(|metrics| metrics.aggregate_by_name().timestamps_removed())
+            .unwrap_or_default();
+
+        Self {
+            operator: plan.name().to_string(),
+            description: displayable(plan).one_line().to_string().trim().to_string(),
+            depth,
+            output_rows: metrics.output_rows(),
+            elapsed_compute: metrics.elapsed_compute().map(|nanos| nanos as f64 / 1e9),
+            bytes_scanned: metrics.sum_by_name(BYTES_SCANNED).map(|v| v.as_usize()),
+            files_pruned,
+            metrics: metrics
+                .iter()
+                .map(|metric| (metric.value().name().to_string(), metric.value().as_usize()))
+                .collect(),
+        }
+    }
+}
+
+#[pymethods]
+impl OperatorMetrics {
+    fn __repr__(&self) -> String {
+        format!(
+            "OperatorMetrics(operator={:?}, depth={}, output_rows={}, elapsed_compute={})",
+            self.operator,
+            self.depth,
+            py_repr(self.output_rows),
+            py_repr(self.elapsed_compute)
+        )
+    }
+}
+
+/// Format an optional value as Python would, e.g. `None` rather than Rust's `Some(1)`.
+fn py_repr<T: std::fmt::Display>(value: Option<T>) -> String {
+    value.map_or_else(|| "None".to_string(), |value| value.to_string())
+}
+
+/// The plans of a query, and with `analyze` the metrics from executing it.
+#[pyclass(name = "QueryPlan", frozen, get_all)]
+#[derive(Debug, Clone)]
+pub(crate) struct QueryPlan {
+    /// The optimized logical plan.
+    logical_plan: String,
+    /// The physical plan, annotated with each operator's metrics if analyzed.
+    physical_plan: String,
+    /// The metrics of each operator, in plan order, if analyzed.
+    metrics: Option<Vec<OperatorMetrics>>,
+    /// The number of files read while executing the query, if analyzed.
+    files_opened: Option<usize>,
+    /// The number of bytes read from files while executing the query, if analyzed.
+    bytes_read: Option<u64>,
+}
+
+impl QueryPlan {
+    /// Describe the plans without executing them.
+    pub(crate) fn new(
+        logical_plan: String,
+        physical_plan: &dyn ExecutionPlan,
+        verbose: bool,
+    ) -> Self {
+        let physical_plan = DisplayableExecutionPlan::new(physical_plan)
+            .set_show_schema(verbose)
+            .indent(verbose)
+            .to_string();
+
+        Self {
+            logical_plan,
+            physical_plan,
+            metrics: None,
+            files_opened: None,
+            bytes_read: None,
+        }
+    }
+
+    /// Describe the plans of an executed query, along with the metrics it recorded.
+    ///
+    /// The plan's scans are expected to be wrapped by [`CountedScanExec`], and the `counters`
+    /// to be those of its object stores.
+    pub(crate) fn analyzed(
+        logical_plan: String,
+        physical_plan: &Arc<dyn ExecutionPlan>,
+        verbose: bool,
+        counters: &ScanCounters,
+    ) -> Self {
+        let displayable = if verbose {
+            DisplayableExecutionPlan::with_full_metrics(physical_plan.as_ref())
+        } else {
+            DisplayableExecutionPlan::with_metrics(physical_plan.as_ref())
+        };
+
+        let only_scan = count_scans(physical_plan.as_ref()) == 1;
+
+        let mut metrics = Vec::new();
+        collect_operator_metrics(physical_plan, 0, counters, only_scan, &mut metrics);
+
+        Self {
+            logical_plan,
+            physical_plan: displayable
+                .set_show_schema(verbose)
+                .indent(verbose)
+                .to_string(),
+            metrics: Some(metrics),
+            files_opened: Some(counters.files_opened()),
+            bytes_read: Some(counters.bytes_read()),
+        }
+    }
+
+    /// Render the per-operator metrics as a table.
+    fn format_metrics(metrics: &[OperatorMetrics]) -> BioBearResult<String> {
+        let operators = metrics
+            .iter()
+            .map(|m| format!("{}{}", "  ".repeat(m.depth), m.operator))
+            .collect::<Vec<_>>();
+
+        let columns: Vec<(&str, ArrayRef)> = vec![
+            ("operator", Arc::new(StringArray::from(operators))),
+            (
+                "output_rows",
+                Arc::new(UInt64Array::from_iter(
+                    metrics.iter().map(|m| m.output_rows.map(|v| v as u64)),
+                )),
+            ),
+            (
+                "elapsed_compute_s",
+                Arc::new(Float64Array::from_iter(
+                    metrics.iter().map(|m| m.elapsed_compute),
+                )),
+            ),
+            (
+                "bytes_scanned",
+                Arc::new(UInt64Array::from_iter(
+                    metrics.iter().map(|m| m.bytes_scanned.map(|v| v as u64)),
+                )),
+            ),
+            (
+                "files_pruned",
+                Arc::new(UInt64Array::from_iter(
+                    metrics.iter().map(|m| m.files_pruned.map(|v| v as u64)),
+                )),
+            ),
+        ];
+
+        let batch = RecordBatch::try_from_iter(columns)?;
+        let table = pretty_format_batches(&[batch])?;
+
+        Ok(table.to_string())
+    }
+}
+
+#[pymethods]
+impl QueryPlan {
+    fn __str__(&self) -> PyResult<String> {
+        let mut text = format!(
+            "Logical Plan\n{}\n\nPhysical Plan\n{}",
+            self.logical_plan,
+            self.physical_plan.trim_end()
+        );
+
+        if let Some(metrics) = &self.metrics {
+            text.push_str("\n\nMetrics\n");
+            text.push_str(&Self::format_metrics(metrics)?);
+        }
+
+        if let (Some(files_opened), Some(bytes_read)) = (self.files_opened, self.bytes_read) {
+            text.push_str(&format!(
+                "\nFiles opened: {files_opened}, bytes read: {bytes_read}"
+            ));
+        }
+
+        Ok(text)
+    }
+
+    fn __repr__(&self) -> PyResult<String> {
+        self.__str__()
+    }
+}
+
+/// Collect the metrics of the plan's operators, depth first.
+fn collect_operator_metrics(
+    plan: &Arc<dyn ExecutionPlan>,
+    depth: usize,
+    counters: &ScanCounters,
+    only_scan: bool,
+    metrics: &mut Vec<OperatorMetrics>,
+) {
+    let files_pruned = plan
+        .as_any()
+        .downcast_ref::<CountedScanExec>()
+        .and_then(|scan| counters.files_pruned(scan.counters(), only_scan));
+
+    metrics.push(OperatorMetrics::new(plan.as_ref(), depth, files_pruned));
+
+    for child in plan.children() {
+        collect_operator_metrics(child, depth + 1, counters, only_scan, metrics);
+    }
+}
+
+/// Count the plan's scans, i.e. its leaves.
+fn count_scans(plan: &dyn ExecutionPlan) -> usize {
+    let children = plan.children();
+
+    if children.is_empty() {
+        return 1;
+    }
+
+    children
+        .iter()
+        .map(|child| count_scans(child.as_ref()))
+        .sum()
+}
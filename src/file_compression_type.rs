@@ -22,21 +22,16 @@ use pyo3::prelude::*;
 
 use crate::error::BioBearError;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default)]
 #[pyclass(eq, eq_int)]
 pub enum FileCompressionType {
     GZIP,
     ZSTD,
+    #[default]
     UNCOMPRESSED,
     BZIP2,
 }
 
-impl Default for FileCompressionType {
-    fn default() -> Self {
-        Self::UNCOMPRESSED
-    }
-}
-
 #[pymethods]
 impl FileCompressionType {
     #[new]
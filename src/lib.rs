@@ -12,12 +12,21 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+// pyo3's generated wrappers convert the `PyErr` of each `PyResult` into itself, which newer
+// clippy flags, so the lint is allowed in the modules with methods returning a `PyResult`.
+#[allow(clippy::useless_conversion)]
 mod runtime;
 
+#[allow(clippy::useless_conversion)]
 mod bam_reader;
+#[allow(clippy::useless_conversion)]
 mod bcf_reader;
 mod datasources;
+mod describe;
+mod display;
+#[allow(clippy::useless_conversion)]
 mod exon_reader;
+#[allow(clippy::useless_conversion)]
 mod vcf_reader;
 
 mod file_compression_type;
@@ -26,27 +35,34 @@ mod file_options;
 pub use file_compression_type::FileCompressionType;
 
 pub(crate) mod error;
+#[allow(clippy::useless_conversion)]
 mod execution_result;
+mod explain;
+mod object_stores;
+mod params;
+#[allow(clippy::useless_conversion)]
+mod prepared_query;
+mod progress;
+mod py_table;
+mod pycapsule;
+mod record_batch_stream;
+mod script;
+mod session_config;
+#[allow(clippy::useless_conversion)]
 mod session_context;
-
-use std::sync::atomic::{AtomicU64, Ordering};
+mod sql_expr;
+mod udfs;
+mod write;
 
 use pyo3::prelude::*;
-use runtime::TokioRuntime;
-use tokio::runtime::Builder;
 
 #[pymodule]
-fn biobear(_py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
-    let runtime = Builder::new_multi_thread()
-        .thread_name_fn(move || {
-            static THREAD_ID: AtomicU64 = AtomicU64::new(0);
-            let id = THREAD_ID.fetch_add(1, Ordering::Relaxed);
-            format!("biobear-python-thread-{}", id)
-        })
-        .enable_all()
-        .build()?;
+fn biobear(py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(runtime::configure_runtime, m)?)?;
 
-    m.add("__runtime", TokioRuntime(runtime))?;
+    let shutdown_async_tasks = wrap_pyfunction!(runtime::shutdown_async_tasks, m)?;
+    py.import_bound("atexit")?
+        .call_method1("register", (shutdown_async_tasks,))?;
 
     m.add_class::<exon_reader::ExonReader>()?;
 
@@ -71,6 +87,18 @@ fn biobear(_py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<datasources::cram::CRAMReadOptions>()?;
     m.add_class::<datasources::fcs::FCSReadOptions>()?;
     m.add_class::<datasources::sdf::SDFReadOptions>()?;
+    m.add_class::<session_config::BioBearSessionConfig>()?;
+    m.add_class::<prepared_query::PreparedQuery>()?;
+    m.add_class::<record_batch_stream::RecordBatchStream>()?;
+    m.add_class::<record_batch_stream::RecordBatchIterator>()?;
+    m.add_class::<progress::Progress>()?;
+    m.add_class::<explain::QueryPlan>()?;
+    m.add_class::<explain::OperatorMetrics>()?;
+    m.add("PlanningError", py.get_type_bound::<error::PlanningError>())?;
+    m.add(
+        "SQLSyntaxError",
+        py.get_type_bound::<error::SQLSyntaxError>(),
+    )?;
 
     m.add_function(wrap_pyfunction!(session_context::connect, m)?)?;
     m.add_function(wrap_pyfunction!(session_context::new_session, m)?)?;
@@ -0,0 +1,266 @@
+// Copyright 2024 WHERE TRUE Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use datafusion::execution::object_store::ObjectStoreUrl;
+use object_store::{
+    aws::AmazonS3Builder, azure::MicrosoftAzureBuilder, gcp::GoogleCloudStorageBuilder, ObjectStore,
+};
+
+use crate::error::{BioBearError, BioBearResult};
+
+/// An object store along with the URL it should be registered under.
+pub(crate) struct ObjectStoreRegistration {
+    pub(crate) url: ObjectStoreUrl,
+    pub(crate) store: Arc<dyn ObjectStore>,
+}
+
+fn invalid_configuration(e: impl ToString) -> BioBearError {
+    BioBearError::InvalidConfiguration(e.to_string())
+}
+
+/// Explicit settings for an S3 (or S3-compatible, e.g. MinIO) bucket.
+///
+/// Without credentials or an endpoint the rest of the configuration is read from the standard
+/// `AWS_*` environment variables, and with them the environment is ignored, so e.g. an ambient
+/// `AWS_SESSION_TOKEN` isn't sent along with a MinIO access key.
+#[derive(Debug, Default)]
+pub(crate) struct S3Options {
+    pub(crate) bucket: String,
+    pub(crate) endpoint: Option<String>,
+    pub(crate) region: Option<String>,
+    pub(crate) access_key: Option<String>,
+    pub(crate) secret_key: Option<String>,
+    pub(crate) session_token: Option<String>,
+    pub(crate) allow_http: bool,
+}
+
+impl S3Options {
+    pub(crate) fn build(self) -> BioBearResult<ObjectStoreRegistration> {
+        let url = ObjectStoreUrl::parse(format!("s3://{}", self.bucket))
+            .map_err(invalid_configuration)?;
+
+        let store = self.builder().build().map_err(invalid_configuration)?;
+
+        Ok(ObjectStoreRegistration {
+            url,
+            store: Arc::new(store),
+        })
+    }
+
+    fn builder(self) -> AmazonS3Builder {
+        let explicit = self.endpoint.is_some()
+            || self.access_key.is_some()
+            || self.secret_key.is_some()
+            || self.session_token.is_some();
+
+        let builder = if explicit {
+            AmazonS3Builder::new()
+        } else {
+            AmazonS3Builder::from_env()
+        };
+
+        let mut builder = builder
+            .with_bucket_name(self.bucket)
+            .with_allow_http(self.allow_http);
+
+        if let Some(endpoint) = self.endpoint {
+            builder = builder.with_endpoint(endpoint);
+        }
+
+        if let Some(region) = self.region {
+            builder = builder.with_region(region);
+        }
+
+        if let Some(access_key) = self.access_key {
+            builder = builder.with_access_key_id(access_key);
+        }
+
+        if let Some(secret_key) = self.secret_key {
+            builder = builder.with_secret_access_key(secret_key);
+        }
+
+        if let Some(session_token) = self.session_token {
+            builder = builder.with_token(session_token);
+        }
+
+        builder
+    }
+}
+
+/// Explicit settings for a Google Cloud Storage bucket.
+///
+/// Without a service account the rest of the configuration is read from the standard `GOOGLE_*`
+/// environment variables, and with one the environment is ignored.
+#[derive(Debug, Default)]
+pub(crate) struct GcsOptions {
+    pub(crate) bucket: String,
+    pub(crate) service_account_path: Option<String>,
+    pub(crate) service_account_key: Option<String>,
+}
+
+impl GcsOptions {
+    pub(crate) fn build(self) -> BioBearResult<ObjectStoreRegistration> {
+        let url = ObjectStoreUrl::parse(format!("gs://{}", self.bucket))
+            .map_err(invalid_configuration)?;
+
+        let store = self.builder().build().map_err(invalid_configuration)?;
+
+        Ok(ObjectStoreRegistration {
+            url,
+            store: Arc::new(store),
+        })
+    }
+
+    fn builder(self) -> GoogleCloudStorageBuilder {
+        let explicit = self.service_account_path.is_some() || self.service_account_key.is_some();
+
+        let builder = if explicit {
+            GoogleCloudStorageBuilder::new()
+        } else {
+            GoogleCloudStorageBuilder::from_env()
+        };
+
+        let mut builder = builder.with_bucket_name(self.bucket);
+
+        if let Some(service_account_path) = self.service_account_path {
+            builder = builder.with_service_account_path(service_account_path);
+        }
+
+        if let Some(service_account_key) = self.service_account_key {
+            builder = builder.with_service_account_key(service_account_key);
+        }
+
+        builder
+    }
+}
+
+/// Explicit settings for an Azure Blob Storage container, registered under `az://<container>`.
+///
+/// Without an access key or an endpoint the rest of the configuration is read from the standard
+/// `AZURE_*` environment variables, and with them the environment is ignored.
+#[derive(Debug, Default)]
+pub(crate) struct AzureOptions {
+    pub(crate) account: String,
+    pub(crate) container: String,
+    pub(crate) access_key: Option<String>,
+    pub(crate) endpoint: Option<String>,
+    pub(crate) allow_http: bool,
+}
+
+impl AzureOptions {
+    pub(crate) fn build(self) -> BioBearResult<ObjectStoreRegistration> {
+        let url = ObjectStoreUrl::parse(format!("az://{}", self.container))
+            .map_err(invalid_configuration)?;
+
+        let store = self.builder().build().map_err(invalid_configuration)?;
+
+        Ok(ObjectStoreRegistration {
+            url,
+            store: Arc::new(store),
+        })
+    }
+
+    fn builder(self) -> MicrosoftAzureBuilder {
+        let explicit = self.access_key.is_some() || self.endpoint.is_some();
+
+        let builder = if explicit {
+            MicrosoftAzureBuilder::new()
+        } else {
+            MicrosoftAzureBuilder::from_env()
+        };
+
+        let mut builder = builder
+            .with_account(self.account)
+            .with_container_name(self.container)
+            .with_allow_http(self.allow_http);
+
+        if let Some(access_key) = self.access_key {
+            builder = builder.with_access_key(access_key);
+        }
+
+        if let Some(endpoint) = self.endpoint {
+            builder = builder.with_endpoint(endpoint);
+        }
+
+        builder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use object_store::aws::AmazonS3ConfigKey;
+
+    use super::*;
+
+    #[test]
+    fn test_s3_options() -> BioBearResult<()> {
+        let registration = S3Options {
+            bucket: "test-bucket".to_string(),
+            endpoint: Some("http://localhost:4566".to_string()),
+            region: Some("us-east-1".to_string()),
+            access_key: Some("test".to_string()),
+            secret_key: Some("test".to_string()),
+            allow_http: true,
+            ..Default::default()
+        }
+        .build()?;
+
+        assert_eq!(registration.url.as_str(), "s3://test-bucket/");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_explicit_s3_options_ignore_environment() {
+        std::env::set_var("AWS_SESSION_TOKEN", "ambient-token");
+        std::env::set_var("AWS_ENDPOINT", "http://ambient.example.com");
+
+        let builder = S3Options {
+            bucket: "test-bucket".to_string(),
+            endpoint: Some("http://localhost:9000".to_string()),
+            access_key: Some("minio".to_string()),
+            secret_key: Some("minio-secret".to_string()),
+            ..Default::default()
+        }
+        .builder();
+
+        std::env::remove_var("AWS_SESSION_TOKEN");
+        std::env::remove_var("AWS_ENDPOINT");
+
+        assert_eq!(
+            builder.get_config_value(&AmazonS3ConfigKey::Endpoint),
+            Some("http://localhost:9000".to_string())
+        );
+        assert_eq!(
+            builder.get_config_value(&AmazonS3ConfigKey::AccessKeyId),
+            Some("minio".to_string())
+        );
+        assert_eq!(builder.get_config_value(&AmazonS3ConfigKey::Token), None);
+    }
+
+    #[test]
+    fn test_invalid_bucket_name() {
+        let options = S3Options {
+            bucket: "not a bucket".to_string(),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            options.build(),
+            Err(BioBearError::InvalidConfiguration(_))
+        ));
+    }
+}
@@ -0,0 +1,182 @@
+// Copyright 2024 WHERE TRUE Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use arrow::{
+    array::{make_array, ArrayData},
+    pyarrow::FromPyArrow,
+};
+use datafusion::{
+    common::{ParamValues, ScalarValue},
+    error::DataFusionError,
+    prelude::DataFrame,
+};
+use pyo3::{
+    prelude::*,
+    types::{PyBool, PyBytes, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple},
+};
+
+use crate::error::{BioBearError, BioBearResult};
+
+/// Convert a Python value into a typed [`ScalarValue`].
+///
+/// Python `bool`, `int`, `float`, `str`, `bytes` and `None` map to Boolean, Int64, Float64, Utf8,
+/// Binary and Null respectively. pyarrow Scalars keep their Arrow type.
+pub(crate) fn py_to_scalar_value(value: &Bound<'_, PyAny>) -> PyResult<ScalarValue> {
+    if value.is_none() {
+        return Ok(ScalarValue::Null);
+    }
+
+    // Check bool before int, as Python bools are also ints.
+    if value.is_instance_of::<PyBool>() {
+        return Ok(ScalarValue::Boolean(Some(value.extract()?)));
+    }
+
+    if value.is_instance_of::<PyInt>() {
+        return Ok(ScalarValue::Int64(Some(value.extract()?)));
+    }
+
+    if value.is_instance_of::<PyFloat>() {
+        return Ok(ScalarValue::Float64(Some(value.extract()?)));
+    }
+
+    if value.is_instance_of::<PyString>() {
+        return Ok(ScalarValue::Utf8(Some(value.extract()?)));
+    }
+
+    if value.is_instance_of::<PyBytes>() {
+        return Ok(ScalarValue::Binary(Some(value.extract()?)));
+    }
+
+    if value.hasattr("as_py")? && value.hasattr("type")? {
+        let py = value.py();
+        let pyarrow = py.import_bound("pyarrow")?;
+
+        let values = PyList::new_bound(py, [value]);
+        let array = pyarrow.call_method1("array", (values, value.getattr("type")?))?;
+        let array = make_array(ArrayData::from_pyarrow_bound(&array)?);
+
+        let scalar = ScalarValue::try_from_array(&array, 0).map_err(BioBearError::from)?;
+
+        return Ok(scalar);
+    }
+
+    Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!(
+        "Unsupported parameter type: {}",
+        value.get_type().name()?
+    )))
+}
+
+/// Convert query parameters into [`ParamValues`].
+///
+/// A list or tuple binds positional parameters (`$1`, `$2`, ...), and a dict binds named
+/// parameters (`$region`).
+pub(crate) fn py_to_param_values(params: &Bound<'_, PyAny>) -> PyResult<ParamValues> {
+    if let Ok(params) = params.downcast::<PyDict>() {
+        let values = params
+            .iter()
+            .map(|(key, value)| Ok((key.extract::<String>()?, py_to_scalar_value(&value)?)))
+            .collect::<PyResult<HashMap<_, _>>>()?;
+
+        return Ok(ParamValues::Map(values));
+    }
+
+    if params.is_instance_of::<PyList>() || params.is_instance_of::<PyTuple>() {
+        let values = params
+            .iter()?
+            .map(|value| py_to_scalar_value(&value?))
+            .collect::<PyResult<Vec<_>>>()?;
+
+        return Ok(ParamValues::List(values));
+    }
+
+    Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+        "params must be a list, tuple or dict",
+    ))
+}
+
+/// Bind the parameters to the placeholders in the DataFrame's plan.
+///
+/// Values are cast to the type DataFusion inferred for each placeholder where one is known, so
+/// e.g. a Python `int` can be compared against an Int32 column.
+pub(crate) fn bind_param_values(df: DataFrame, params: ParamValues) -> BioBearResult<DataFrame> {
+    let param_types = df.logical_plan().get_parameter_types()?;
+
+    let cast = |id: String, value: ScalarValue| match param_types.get(&id) {
+        Some(Some(data_type)) => value.cast_to(data_type),
+        _ => Ok(value),
+    };
+
+    let params = match params {
+        ParamValues::List(values) => ParamValues::List(
+            values
+                .into_iter()
+                .enumerate()
+                .map(|(i, value)| cast(format!("${}", i + 1), value))
+                .collect::<Result<_, _>>()?,
+        ),
+        ParamValues::Map(values) => ParamValues::Map(
+            values
+                .into_iter()
+                .map(|(name, value)| {
+                    let value = cast(format!("${name}"), value)?;
+                    Ok::<_, DataFusionError>((name, value))
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+    };
+
+    let df = df.with_param_values(params)?;
+
+    Ok(df)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_py_to_param_values() -> PyResult<()> {
+        pyo3::prepare_freethreaded_python();
+
+        Python::with_gil(|py| {
+            let params = PyList::new_bound(py, [1.into_py(py), "chr1".into_py(py), py.None()]);
+
+            let ParamValues::List(values) = py_to_param_values(&params)? else {
+                panic!("expected positional parameters");
+            };
+            assert_eq!(
+                values,
+                vec![
+                    ScalarValue::Int64(Some(1)),
+                    ScalarValue::Utf8(Some("chr1".to_string())),
+                    ScalarValue::Null,
+                ]
+            );
+
+            let params = PyDict::new_bound(py);
+            params.set_item("flag", true)?;
+
+            let ParamValues::Map(values) = py_to_param_values(&params)? else {
+                panic!("expected named parameters");
+            };
+            assert_eq!(values["flag"], ScalarValue::Boolean(Some(true)));
+
+            assert!(py_to_param_values(&PyString::new_bound(py, "not params")).is_err());
+
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,71 @@
+// Copyright 2024 WHERE TRUE Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arrow::{datatypes::DataType, pyarrow::PyArrowType};
+use datafusion::prelude::DataFrame;
+use pyo3::prelude::*;
+
+use crate::{
+    error::BioBearError,
+    execution_result::ExecutionResult,
+    params::{bind_param_values, py_to_param_values},
+};
+
+/// A query whose logical plan is built once and executed with different parameters.
+#[pyclass(name = "PreparedQuery")]
+#[derive(Clone)]
+pub(crate) struct PreparedQuery {
+    df: Arc<DataFrame>,
+}
+
+impl PreparedQuery {
+    pub(crate) fn new(df: DataFrame) -> Self {
+        Self { df: Arc::new(df) }
+    }
+}
+
+#[pymethods]
+impl PreparedQuery {
+    /// Bind the parameters to the planned query and return the result as an [`ExecutionResult`].
+    #[pyo3(signature = (params=None))]
+    fn execute(&self, params: Option<&Bound<'_, PyAny>>) -> PyResult<ExecutionResult> {
+        let df = self.df.as_ref().clone();
+
+        let df = match params.map(py_to_param_values).transpose()? {
+            Some(params) => bind_param_values(df, params)?,
+            None => df,
+        };
+
+        Ok(ExecutionResult::new(df))
+    }
+
+    /// Return the types DataFusion inferred for the placeholders in the query.
+    ///
+    /// Placeholders whose type can't be inferred from the query are omitted.
+    fn parameter_types(&self) -> PyResult<HashMap<String, PyArrowType<DataType>>> {
+        let param_types = self
+            .df
+            .logical_plan()
+            .get_parameter_types()
+            .map_err(BioBearError::from)?;
+
+        Ok(param_types
+            .into_iter()
+            .filter_map(|(name, data_type)| data_type.map(|t| (name, PyArrowType(t))))
+            .collect())
+    }
+}
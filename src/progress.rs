@@ -0,0 +1,941 @@
+// Copyright 2024 WHERE TRUE Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    any::Any,
+    cell::RefCell,
+    collections::HashSet,
+    fmt::{Debug, Display},
+    ops::Range,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, Receiver},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+    task::Poll,
+    time::{Duration, Instant},
+};
+
+use arrow::{
+    array::{RecordBatch, RecordBatchReader},
+    datatypes::SchemaRef,
+    error::ArrowError,
+};
+use async_trait::async_trait;
+use bytes::Bytes;
+use datafusion::{
+    common::tree_node::{Transformed, TreeNode},
+    error::{DataFusionError, Result},
+    execution::{
+        object_store::ObjectStoreRegistry, runtime_env::RuntimeEnv,
+        session_state::SessionStateBuilder, SendableRecordBatchStream, SessionState, TaskContext,
+    },
+    physical_plan::{
+        metrics::{
+            BaselineMetrics, Count, ExecutionPlanMetricsSet, Metric, MetricValue, MetricsSet,
+        },
+        stream::RecordBatchStreamAdapter,
+        DisplayAs, DisplayFormatType, ExecutionPlan, ExecutionPlanProperties, PlanProperties,
+        Statistics,
+    },
+    prelude::DataFrame,
+};
+use exon::ffi::DataFrameRecordBatchStream;
+use futures::{stream::BoxStream, StreamExt, TryStreamExt};
+use object_store::{
+    path::Path, GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta, ObjectStore,
+    PutMultipartOpts, PutOptions, PutPayload, PutResult,
+};
+use pyo3::prelude::*;
+use tokio::runtime::Runtime;
+use url::Url;
+
+/// The metric recording the bytes a scan read, which Parquet scans record themselves.
+pub(crate) const BYTES_SCANNED: &str = "bytes_scanned";
+
+/// Counts the files opened and bytes read through a query's object stores, along with the files
+/// listed while planning it, e.g. those of a table's partitions.
+#[derive(Debug, Default)]
+pub(crate) struct ScanCounters {
+    files: Mutex<HashSet<Path>>,
+    bytes_read: AtomicU64,
+    listings: Mutex<HashSet<Path>>,
+    listed: Mutex<HashSet<Path>>,
+}
+
+impl ScanCounters {
+    fn record_read(&self, location: &Path, bytes: usize) {
+        self.files
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(location.clone());
+        self.bytes_read.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    fn record_listing(&self, prefix: &Path) {
+        self.listings
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(prefix.clone());
+    }
+
+    fn record_listed(&self, location: &Path) {
+        self.listed
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(location.clone());
+    }
+
+    pub(crate) fn files_opened(&self) -> usize {
+        self.files
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    pub(crate) fn bytes_read(&self) -> u64 {
+        self.bytes_read.load(Ordering::Relaxed)
+    }
+
+    /// The number of files listed while planning the query that `scan` didn't read, e.g. as
+    /// their partitions were pruned, or `None` if it read none of the listed tables.
+    ///
+    /// A listing, along with the listings below it, belongs to the scans that read a file below
+    /// it. With `only_scan` every listing belongs to the scan, as it reads no files if they're
+    /// all pruned.
+    pub(crate) fn files_pruned(&self, scan: &ScanCounters, only_scan: bool) -> Option<usize> {
+        let listings = self.listings.lock().unwrap_or_else(PoisonError::into_inner);
+        let listed = self.listed.lock().unwrap_or_else(PoisonError::into_inner);
+        let read = scan.files.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let roots = listings
+            .iter()
+            .filter(|prefix| {
+                !listings
+                    .iter()
+                    .any(|other| other != *prefix && prefix.prefix_matches(other))
+            })
+            .filter(|root| only_scan || read.iter().any(|file| file.prefix_matches(root)))
+            .collect::<Vec<_>>();
+
+        if roots.is_empty() {
+            return None;
+        }
+
+        let pruned = listed
+            .iter()
+            .filter(|file| !read.contains(*file))
+            .filter(|file| roots.iter().any(|root| file.prefix_matches(root)))
+            .count();
+
+        Some(pruned)
+    }
+
+    /// Return a copy of the session state whose object stores record reads on these counters.
+    pub(crate) fn instrument(self: &Arc<Self>, state: SessionState) -> SessionState {
+        let runtime_env = state.runtime_env();
+
+        let object_store_registry = Arc::new(CountingObjectStoreRegistry {
+            inner: runtime_env.object_store_registry.clone(),
+            counters: self.clone(),
+        });
+
+        let runtime_env = RuntimeEnv {
+            memory_pool: runtime_env.memory_pool.clone(),
+            disk_manager: runtime_env.disk_manager.clone(),
+            cache_manager: runtime_env.cache_manager.clone(),
+            object_store_registry,
+        };
+
+        SessionStateBuilder::new_from_existing(state)
+            .with_runtime_env(Arc::new(runtime_env))
+            .build()
+    }
+}
+
+/// An [`ObjectStoreRegistry`] that wraps the stores it returns in a [`CountingObjectStore`].
+#[derive(Debug)]
+struct CountingObjectStoreRegistry {
+    inner: Arc<dyn ObjectStoreRegistry>,
+    counters: Arc<ScanCounters>,
+}
+
+impl ObjectStoreRegistry for CountingObjectStoreRegistry {
+    fn register_store(
+        &self,
+        url: &Url,
+        store: Arc<dyn ObjectStore>,
+    ) -> Option<Arc<dyn ObjectStore>> {
+        self.inner.register_store(url, store)
+    }
+
+    fn get_store(&self, url: &Url) -> Result<Arc<dyn ObjectStore>> {
+        let store = self.inner.get_store(url)?;
+
+        Ok(Arc::new(CountingObjectStore {
+            inner: store,
+            counters: self.counters.clone(),
+        }))
+    }
+}
+
+thread_local! {
+    /// The counters of the [`CountedScanExec`] being polled on this thread, if any.
+    static CURRENT_SCAN: RefCell<Option<Arc<ScanCounters>>> = const { RefCell::new(None) };
+}
+
+/// An [`ObjectStore`] that records the objects and byte ranges read from the inner store, and
+/// the objects listed in it.
+///
+/// Reads are also recorded on the counters of the scan reading them, if it's a
+/// [`CountedScanExec`].
+#[derive(Debug)]
+struct CountingObjectStore {
+    inner: Arc<dyn ObjectStore>,
+    counters: Arc<ScanCounters>,
+}
+
+impl CountingObjectStore {
+    fn record_read(&self, location: &Path, bytes: usize) {
+        self.counters.record_read(location, bytes);
+
+        CURRENT_SCAN.with_borrow(|scan| {
+            if let Some(scan) = scan {
+                scan.record_read(location, bytes);
+            }
+        });
+    }
+}
+
+impl Display for CountingObjectStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "CountingObjectStore({})", self.inner)
+    }
+}
+
+#[async_trait]
+impl ObjectStore for CountingObjectStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> object_store::Result<PutResult> {
+        self.inner.put_opts(location, payload, opts).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> object_store::Result<Box<dyn MultipartUpload>> {
+        self.inner.put_multipart_opts(location, opts).await
+    }
+
+    async fn get_opts(
+        &self,
+        location: &Path,
+        options: GetOptions,
+    ) -> object_store::Result<GetResult> {
+        let result = self.inner.get_opts(location, options).await?;
+
+        // Local files are read directly from the file handle, so count the requested range.
+        self.record_read(location, result.range.end - result.range.start);
+
+        Ok(result)
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> object_store::Result<Bytes> {
+        let bytes = self.inner.get_range(location, range).await?;
+        self.record_read(location, bytes.len());
+
+        Ok(bytes)
+    }
+
+    async fn get_ranges(
+        &self,
+        location: &Path,
+        ranges: &[Range<usize>],
+    ) -> object_store::Result<Vec<Bytes>> {
+        let bytes = self.inner.get_ranges(location, ranges).await?;
+        self.record_read(location, bytes.iter().map(Bytes::len).sum());
+
+        Ok(bytes)
+    }
+
+    async fn head(&self, location: &Path) -> object_store::Result<ObjectMeta> {
+        let meta = self.inner.head(location).await?;
+
+        self.counters.record_listing(location);
+        self.counters.record_listed(&meta.location);
+
+        Ok(meta)
+    }
+
+    async fn delete(&self, location: &Path) -> object_store::Result<()> {
+        self.inner.delete(location).await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, object_store::Result<ObjectMeta>> {
+        self.counters
+            .record_listing(prefix.unwrap_or(&Path::default()));
+
+        self.inner
+            .list(prefix)
+            .inspect_ok(|meta| self.counters.record_listed(&meta.location))
+            .boxed()
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> object_store::Result<ListResult> {
+        let result = self.inner.list_with_delimiter(prefix).await?;
+
+        self.counters
+            .record_listing(prefix.unwrap_or(&Path::default()));
+        for meta in &result.objects {
+            self.counters.record_listed(&meta.location);
+        }
+
+        Ok(result)
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+        self.inner.copy(from, to).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+        self.inner.copy_if_not_exists(from, to).await
+    }
+}
+
+/// A snapshot of a running query's progress, passed to the progress callback.
+#[pyclass(name = "Progress", frozen, get_all)]
+#[derive(Debug, Clone)]
+pub(crate) struct Progress {
+    /// The number of distinct files read from so far.
+    files_opened: usize,
+    /// The number of bytes read from files so far.
+    bytes_read: u64,
+    /// The number of rows produced by the query's scans so far.
+    rows: usize,
+    /// The number of scan partitions that have finished.
+    partitions_completed: usize,
+    /// The total number of scan partitions.
+    partitions_total: usize,
+    /// Seconds since the query started.
+    elapsed: f64,
+    /// Whether the query has finished.
+    finished: bool,
+}
+
+#[pymethods]
+impl Progress {
+    fn __repr__(&self) -> String {
+        format!(
+            "Progress(files_opened={}, bytes_read={}, rows={}, partitions_completed={}, partitions_total={}, elapsed={:.3}, finished={})",
+            self.files_opened,
+            self.bytes_read,
+            self.rows,
+            self.partitions_completed,
+            self.partitions_total,
+            self.elapsed,
+            if self.finished { "True" } else { "False" },
+        )
+    }
+}
+
+/// The Python callback for an [`ExecutionResult`](crate::execution_result::ExecutionResult)'s
+/// progress and how often to call it.
+#[derive(Debug)]
+pub(crate) struct ProgressHook {
+    pub(crate) callback: PyObject,
+    pub(crate) interval: Duration,
+}
+
+/// Reports a query's progress to a [`ProgressHook`], built from the execution metrics of the
+/// plan's scans and the object store counters.
+pub(crate) struct ProgressReporter {
+    hook: Arc<ProgressHook>,
+    counters: Arc<ScanCounters>,
+    scans: Vec<Arc<dyn ExecutionPlan>>,
+    started: Instant,
+    last_report: Option<Instant>,
+}
+
+impl ProgressReporter {
+    /// Wrap the plan's scans so their progress can be reported, returning the plan to execute.
+    pub(crate) fn new(
+        hook: Arc<ProgressHook>,
+        counters: Arc<ScanCounters>,
+        plan: Arc<dyn ExecutionPlan>,
+    ) -> Result<(Self, Arc<dyn ExecutionPlan>)> {
+        let mut scans = Vec::new();
+
+        let plan = plan
+            .transform_up(|node| {
+                if !node.children().is_empty() {
+                    return Ok(Transformed::no(node));
+                }
+
+                let scan: Arc<dyn ExecutionPlan> = Arc::new(MeteredScanExec::new(node));
+                scans.push(scan.clone());
+
+                Ok(Transformed::yes(scan))
+            })?
+            .data;
+
+        let reporter = Self {
+            hook,
+            counters,
+            scans,
+            started: Instant::now(),
+            last_report: None,
+        };
+
+        Ok((reporter, plan))
+    }
+
+    fn snapshot(&self, finished: bool) -> Progress {
+        let mut rows = 0;
+        let mut partitions_completed = 0;
+        let mut partitions_total = 0;
+
+        for scan in &self.scans {
+            partitions_total += scan.output_partitioning().partition_count();
+
+            if let Some(metrics) = scan.metrics() {
+                rows += metrics.output_rows().unwrap_or_default();
+                partitions_completed += completed_partitions(&metrics);
+            }
+        }
+
+        Progress {
+            files_opened: self.counters.files_opened(),
+            bytes_read: self.counters.bytes_read(),
+            rows,
+            partitions_completed,
+            partitions_total,
+            elapsed: self.started.elapsed().as_secs_f64(),
+            finished,
+        }
+    }
+
+    /// Take a snapshot of the current progress if a report is due, as reports are made at most
+    /// once per interval unless finished.
+    fn take_snapshot(&mut self, finished: bool) -> Option<Progress> {
+        let due = finished
+            || self
+                .last_report
+                .is_none_or(|last| last.elapsed() >= self.hook.interval);
+
+        if !due {
+            return None;
+        }
+
+        self.last_report = Some(Instant::now());
+        Some(self.snapshot(finished))
+    }
+
+    /// Call the callback with the current progress, at most once per interval unless finished.
+    pub(crate) fn report(&mut self, py: Python, finished: bool) -> PyResult<()> {
+        if let Some(progress) = self.take_snapshot(finished) {
+            self.hook.callback.call1(py, (progress,))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Create the physical plan of the query and the context to execute it with.
+///
+/// With a hook the plan is created against a copy of the session whose object stores count what
+/// the scans read, and its scans are wrapped so the returned reporter can build the progress from
+/// their metrics and the counters.
+pub(crate) async fn create_physical_plan(
+    df: DataFrame,
+    hook: Option<Arc<ProgressHook>>,
+) -> Result<(
+    Arc<dyn ExecutionPlan>,
+    Arc<TaskContext>,
+    Option<ProgressReporter>,
+)> {
+    let (state, plan) = df.into_parts();
+
+    let Some(hook) = hook else {
+        let physical_plan = state.create_physical_plan(&plan).await?;
+        return Ok((physical_plan, state.task_ctx(), None));
+    };
+
+    let counters = Arc::new(ScanCounters::default());
+    let state = counters.instrument(state);
+
+    let physical_plan = state.create_physical_plan(&plan).await?;
+    let (reporter, physical_plan) = ProgressReporter::new(hook, counters, physical_plan)?;
+
+    Ok((physical_plan, state.task_ctx(), Some(reporter)))
+}
+
+/// A [`ProgressReporter`] shared between the task executing a query, which sets it once the query
+/// is planned, and the thread waiting on the task, which reports the progress.
+///
+/// The query is reported finished once each of its `streams`, e.g. its partitions read by
+/// separate iterators, has finished.
+#[derive(Clone)]
+pub(crate) struct SharedReporter {
+    state: Arc<Mutex<SharedReporterState>>,
+}
+
+struct SharedReporterState {
+    reporter: Option<ProgressReporter>,
+    open_streams: usize,
+    reported_finished: bool,
+}
+
+impl SharedReporter {
+    pub(crate) fn new(streams: usize) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(SharedReporterState {
+                reporter: None,
+                open_streams: streams,
+                reported_finished: false,
+            })),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SharedReporterState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Set the reporter of the planned query, which is `None` without a progress hook.
+    pub(crate) fn set(&self, reporter: Option<ProgressReporter>) {
+        self.lock().reporter = reporter;
+    }
+
+    /// Record that one of the query's streams has finished.
+    pub(crate) fn finish_stream(&self) {
+        let mut state = self.lock();
+        state.open_streams = state.open_streams.saturating_sub(1);
+    }
+
+    /// Call the callback with the current progress if a report is due, or once when every
+    /// stream has finished.
+    pub(crate) fn report(&self, py: Python) -> PyResult<()> {
+        let mut state = self.lock();
+        let finished = state.open_streams == 0;
+
+        if finished && state.reported_finished {
+            return Ok(());
+        }
+        state.reported_finished = finished;
+
+        match state.reporter.as_mut() {
+            Some(reporter) => reporter.report(py, finished),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Report the query's progress as the stream's batches are read, e.g. by a consumer of the Arrow
+/// PyCapsule interface.
+///
+/// Rather than calling the callback while the stream is polled, which would take the GIL on
+/// whichever thread polls it, the snapshots are sent to the returned [`ProgressReceiver`] for
+/// the reading thread to pass on.
+pub(crate) fn report_stream(
+    mut stream: SendableRecordBatchStream,
+    mut reporter: ProgressReporter,
+) -> (SendableRecordBatchStream, ProgressReceiver) {
+    let (sender, snapshots) = mpsc::channel();
+    let receiver = ProgressReceiver {
+        hook: reporter.hook.clone(),
+        snapshots,
+    };
+
+    let schema = stream.schema();
+    let mut finished = false;
+
+    let stream = futures::stream::poll_fn(move |cx| {
+        if finished {
+            return Poll::Ready(None);
+        }
+
+        let poll = stream.poll_next_unpin(cx);
+        finished = match &poll {
+            Poll::Ready(None) => true,
+            Poll::Ready(Some(Ok(_))) => false,
+            _ => return poll,
+        };
+
+        if let Some(progress) = reporter.take_snapshot(finished) {
+            // The receiver is only dropped along with the reader polling this stream.
+            let _ = sender.send(progress);
+        }
+
+        poll
+    });
+
+    (
+        Box::pin(RecordBatchStreamAdapter::new(schema, stream)),
+        receiver,
+    )
+}
+
+/// Receives the progress reported by a stream wrapped with [`report_stream`].
+pub(crate) struct ProgressReceiver {
+    hook: Arc<ProgressHook>,
+    snapshots: Receiver<Progress>,
+}
+
+impl ProgressReceiver {
+    /// Call the callback with each snapshot received since the last call.
+    fn report(&self, py: Python) -> PyResult<()> {
+        for progress in self.snapshots.try_iter() {
+            self.hook.callback.call1(py, (progress,))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads a query's stream on the shared runtime, e.g. for the Arrow PyCapsule interface, and
+/// passes the progress received from [`report_stream`] to the callback between batches, on the
+/// reading thread.
+///
+/// An exception raised by the callback ends the reader with an error.
+pub(crate) struct ProgressRecordBatchReader {
+    inner: DataFrameRecordBatchStream,
+    schema: SchemaRef,
+    progress: Option<ProgressReceiver>,
+    finished: bool,
+}
+
+impl ProgressRecordBatchReader {
+    pub(crate) fn new(
+        stream: SendableRecordBatchStream,
+        runtime: Arc<Runtime>,
+        progress: Option<ProgressReceiver>,
+    ) -> Self {
+        Self {
+            schema: stream.schema(),
+            inner: DataFrameRecordBatchStream::new(stream, runtime),
+            progress,
+            finished: false,
+        }
+    }
+}
+
+impl Iterator for ProgressRecordBatchReader {
+    type Item = std::result::Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let batch = self.inner.next();
+
+        if let Some(progress) = &self.progress {
+            if let Err(err) = Python::with_gil(|py| progress.report(py)) {
+                self.finished = true;
+                return Some(Err(ArrowError::ExternalError(Box::new(err))));
+            }
+        }
+
+        self.finished = batch.is_none();
+        batch
+    }
+}
+
+impl RecordBatchReader for ProgressRecordBatchReader {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+/// The number of distinct partitions that have recorded an end time, as a scan may be executed
+/// more than once, e.g. the work table of a recursive query.
+fn completed_partitions(metrics: &MetricsSet) -> usize {
+    metrics
+        .iter()
+        .filter(|metric| {
+            matches!(metric.value(), MetricValue::EndTimestamp(end) if end.value().is_some())
+        })
+        .filter_map(|metric| metric.partition())
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+/// Records the rows a scan produces and when each of its partitions finishes, as not every scan
+/// reports its own metrics.
+#[derive(Debug)]
+struct MeteredScanExec {
+    input: Arc<dyn ExecutionPlan>,
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl MeteredScanExec {
+    fn new(input: Arc<dyn ExecutionPlan>) -> Self {
+        Self {
+            input,
+            metrics: ExecutionPlanMetricsSet::new(),
+        }
+    }
+}
+
+impl DisplayAs for MeteredScanExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "MeteredScanExec")
+    }
+}
+
+impl ExecutionPlan for MeteredScanExec {
+    fn name(&self) -> &str {
+        "MeteredScanExec"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        self.input.properties()
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn ExecutionPlan>> {
+        vec![&self.input]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let [input] = <[_; 1]>::try_from(children).map_err(|_| {
+            DataFusionError::Internal("MeteredScanExec expects a single child".to_string())
+        })?;
+
+        Ok(Arc::new(Self {
+            input,
+            metrics: self.metrics.clone(),
+        }))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let mut stream = self.input.execute(partition, context)?;
+        let schema = stream.schema();
+        let baseline = BaselineMetrics::new(&self.metrics, partition);
+
+        let stream = futures::stream::poll_fn(move |cx| {
+            let poll = stream.poll_next_unpin(cx);
+            baseline.record_poll(poll)
+        });
+
+        Ok(Box::pin(RecordBatchStreamAdapter::new(schema, stream)))
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+}
+
+/// Records the files and bytes a scan reads through the query's [`CountingObjectStore`]s, by
+/// making its counters current while the scan's streams are polled.
+///
+/// Unlike [`MeteredScanExec`] it stands in for the scan, taking its name, display and children,
+/// so an analyzed plan shows the scan with a `bytes_scanned` metric, which only Parquet scans
+/// record themselves.
+#[derive(Debug)]
+pub(crate) struct CountedScanExec {
+    input: Arc<dyn ExecutionPlan>,
+    counters: Arc<ScanCounters>,
+}
+
+impl CountedScanExec {
+    /// Wrap the plan's scans, returning the plan to execute.
+    pub(crate) fn wrap_scans(plan: Arc<dyn ExecutionPlan>) -> Result<Arc<dyn ExecutionPlan>> {
+        let plan = plan
+            .transform_up(|node| {
+                if !node.children().is_empty() {
+                    return Ok(Transformed::no(node));
+                }
+
+                let scan: Arc<dyn ExecutionPlan> = Arc::new(Self {
+                    input: node,
+                    counters: Arc::new(ScanCounters::default()),
+                });
+
+                Ok(Transformed::yes(scan))
+            })?
+            .data;
+
+        Ok(plan)
+    }
+
+    /// The files and bytes the scan read.
+    pub(crate) fn counters(&self) -> &ScanCounters {
+        &self.counters
+    }
+}
+
+/// Call `f` with the scan's counters recording the reads made on this thread.
+fn with_current_scan<T>(counters: &Arc<ScanCounters>, f: impl FnOnce() -> T) -> T {
+    let previous = CURRENT_SCAN.replace(Some(counters.clone()));
+    let result = f();
+    CURRENT_SCAN.set(previous);
+
+    result
+}
+
+impl DisplayAs for CountedScanExec {
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.input.fmt_as(t, f)
+    }
+}
+
+impl ExecutionPlan for CountedScanExec {
+    fn name(&self) -> &str {
+        self.input.name()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        self.input.properties()
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn ExecutionPlan>> {
+        self.input.children()
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(Self {
+            input: self.input.clone().with_new_children(children)?,
+            counters: self.counters.clone(),
+        }))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let mut stream =
+            with_current_scan(&self.counters, || self.input.execute(partition, context))?;
+        let schema = stream.schema();
+        let counters = self.counters.clone();
+
+        let stream = futures::stream::poll_fn(move |cx| {
+            with_current_scan(&counters, || stream.poll_next_unpin(cx))
+        });
+
+        Ok(Box::pin(RecordBatchStreamAdapter::new(schema, stream)))
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        let metrics = self.input.metrics();
+
+        let records_bytes = metrics.as_ref().is_some_and(|metrics| {
+            metrics
+                .iter()
+                .any(|metric| metric.value().name() == BYTES_SCANNED)
+        });
+        if records_bytes || self.counters.files_opened() == 0 {
+            return metrics;
+        }
+
+        let bytes_scanned = Count::new();
+        bytes_scanned.add(self.counters.bytes_read() as usize);
+
+        let mut metrics = metrics.unwrap_or_default();
+        metrics.push(Arc::new(Metric::new(
+            MetricValue::Count {
+                name: BYTES_SCANNED.into(),
+                count: bytes_scanned,
+            },
+            None,
+        )));
+
+        Some(metrics)
+    }
+
+    fn statistics(&self) -> Result<Statistics> {
+        self.input.statistics()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use object_store::memory::InMemory;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_counting_object_store() -> object_store::Result<()> {
+        let counters = Arc::new(ScanCounters::default());
+        let store = CountingObjectStore {
+            inner: Arc::new(InMemory::new()),
+            counters: counters.clone(),
+        };
+
+        let location = Path::from("test.fasta");
+        store.put(&location, "ACGTACGT".into()).await?;
+
+        store.get_range(&location, 0..4).await?;
+        store.get(&location).await?.bytes().await?;
+
+        assert_eq!(counters.files_opened(), 1);
+        assert_eq!(counters.bytes_read(), 12);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_files_pruned() -> object_store::Result<()> {
+        let counters = Arc::new(ScanCounters::default());
+        let store = CountingObjectStore {
+            inner: Arc::new(InMemory::new()),
+            counters: counters.clone(),
+        };
+
+        for part in ["part=1", "part=2"] {
+            let location = Path::from(format!("table/{part}/test.fasta"));
+            store.put(&location, "ACGT".into()).await?;
+        }
+        store
+            .list(Some(&Path::from("table")))
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        let scan = Arc::new(ScanCounters::default());
+        let location = Path::from("table/part=1/test.fasta");
+        with_current_scan(&scan, || futures::executor::block_on(store.get(&location)))?;
+
+        assert_eq!(scan.bytes_read(), 4);
+        assert_eq!(counters.files_pruned(&scan, false), Some(1));
+        assert_eq!(counters.files_pruned(&ScanCounters::default(), false), None);
+        assert_eq!(
+            counters.files_pruned(&ScanCounters::default(), true),
+            Some(2)
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,195 @@
+// Copyright 2024 WHERE TRUE Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::{Arc, Mutex};
+
+use arrow::{
+    datatypes::SchemaRef, ffi_stream::ArrowArrayStreamReader, pyarrow::FromPyArrow,
+    record_batch::RecordBatchReader,
+};
+use datafusion::{
+    catalog::TableProvider,
+    datasource::{streaming::StreamingTable, MemTable},
+    error::DataFusionError,
+    execution::{SendableRecordBatchStream, TaskContext},
+    physical_plan::{stream::RecordBatchReceiverStreamBuilder, streaming::PartitionStream},
+};
+use pyo3::{prelude::*, types::PyAnyMethods};
+
+use crate::error::BioBearError;
+
+/// The number of batches buffered ahead of the consumer of a streaming table.
+const STREAM_BUFFER_SIZE: usize = 2;
+
+/// A [`PartitionStream`] that reads from an Arrow stream exactly once.
+///
+/// Streams coming from Python (e.g. a `pyarrow.RecordBatchReader`) can't be rewound, so a second
+/// scan of the table returns an error rather than silently returning no rows.
+pub(crate) struct OneShotPartitionStream {
+    schema: SchemaRef,
+    reader: Mutex<Option<ArrowArrayStreamReader>>,
+}
+
+impl OneShotPartitionStream {
+    pub(crate) fn new(reader: ArrowArrayStreamReader) -> Self {
+        Self {
+            schema: reader.schema(),
+            reader: Mutex::new(Some(reader)),
+        }
+    }
+}
+
+impl std::fmt::Debug for OneShotPartitionStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OneShotPartitionStream")
+            .field("schema", &self.schema)
+            .finish()
+    }
+}
+
+impl PartitionStream for OneShotPartitionStream {
+    fn schema(&self) -> &SchemaRef {
+        &self.schema
+    }
+
+    fn execute(&self, _ctx: Arc<TaskContext>) -> SendableRecordBatchStream {
+        let reader = self.reader.lock().ok().and_then(|mut r| r.take());
+
+        let mut builder =
+            RecordBatchReceiverStreamBuilder::new(self.schema.clone(), STREAM_BUFFER_SIZE);
+        let tx = builder.tx();
+
+        builder.spawn_blocking(move || {
+            let reader = reader.ok_or_else(|| {
+                DataFusionError::Execution(
+                    "Streaming table has already been consumed and can only be scanned once"
+                        .to_string(),
+                )
+            })?;
+
+            for batch in reader {
+                let batch = batch.map_err(DataFusionError::from);
+
+                // The receiver is gone, so the query was cancelled.
+                if tx.blocking_send(batch).is_err() {
+                    break;
+                }
+            }
+
+            Ok(())
+        });
+
+        builder.build()
+    }
+}
+
+/// Returns true if the object is a `pyarrow.RecordBatchReader`.
+fn is_record_batch_reader(obj: &Bound<'_, PyAny>) -> PyResult<bool> {
+    let py = obj.py();
+
+    match py.import_bound("pyarrow") {
+        Ok(pyarrow) => obj.is_instance(&pyarrow.getattr("RecordBatchReader")?),
+        Err(_) => Ok(false),
+    }
+}
+
+/// Returns true if the object is an iterator (e.g. a generator) that isn't already a reader.
+fn is_batch_iterator(obj: &Bound<'_, PyAny>) -> PyResult<bool> {
+    Ok(obj.hasattr("__next__")? && !is_record_batch_reader(obj)?)
+}
+
+/// Wrap an iterator of pyarrow RecordBatches in a `pyarrow.RecordBatchReader`.
+///
+/// The schema is taken from the first batch, so the iterator must yield at least one batch.
+fn reader_from_batch_iterator<'py>(obj: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+    let py = obj.py();
+
+    let first = match obj.call_method0("__next__") {
+        Ok(first) => first,
+        Err(e) if e.is_instance_of::<pyo3::exceptions::PyStopIteration>(py) => {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "Cannot infer a schema from an iterator that yields no batches",
+            ));
+        }
+        Err(e) => return Err(e),
+    };
+
+    let schema = first.getattr("schema")?;
+    let batches = py
+        .import_bound("itertools")?
+        .call_method1("chain", ((first,), obj))?;
+
+    let reader_class = py.import_bound("pyarrow")?.getattr("RecordBatchReader")?;
+    reader_class.call_method1("from_batches", (schema, batches))
+}
+
+/// Convert a Python object into an Arrow stream reader.
+///
+/// Anything implementing the Arrow PyCapsule stream interface is accepted. Older Polars and
+/// pandas versions that don't implement it are converted through pyarrow first.
+fn to_stream_reader(obj: &Bound<'_, PyAny>) -> PyResult<ArrowArrayStreamReader> {
+    if obj.hasattr("__arrow_c_stream__")? || is_record_batch_reader(obj)? {
+        return ArrowArrayStreamReader::from_pyarrow_bound(obj);
+    }
+
+    let py = obj.py();
+    let module = obj.get_type().module()?.to_string();
+
+    let table = if module.starts_with("pandas") {
+        let table_class = py.import_bound("pyarrow")?.getattr("Table")?;
+        table_class.call_method1("from_pandas", (obj,))?
+    } else if obj.hasattr("to_arrow")? {
+        obj.call_method0("to_arrow")?
+    } else {
+        return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!(
+            "Cannot register object of type {} as a table, expected an object implementing __arrow_c_stream__",
+            obj.get_type().name()?
+        )));
+    };
+
+    ArrowArrayStreamReader::from_pyarrow_bound(&table.into_any())
+}
+
+/// Build a [`TableProvider`] from an in-memory Python object.
+///
+/// Record batch readers and iterators of batches are registered as one-shot streaming tables,
+/// everything else is collected into a [`MemTable`].
+pub(crate) fn table_provider_from_pyobject(
+    obj: &Bound<'_, PyAny>,
+) -> PyResult<Arc<dyn TableProvider>> {
+    if is_batch_iterator(obj)? {
+        let reader = reader_from_batch_iterator(obj)?;
+        return table_provider_from_pyobject(&reader);
+    }
+
+    let streaming = is_record_batch_reader(obj)?;
+    let reader = to_stream_reader(obj)?;
+
+    if streaming {
+        let partition = Arc::new(OneShotPartitionStream::new(reader));
+        let table = StreamingTable::try_new(partition.schema().clone(), vec![partition])
+            .map_err(BioBearError::from)?;
+
+        return Ok(Arc::new(table));
+    }
+
+    let schema = reader.schema();
+    let batches = reader
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(BioBearError::from)?;
+
+    let table = MemTable::try_new(schema, vec![batches]).map_err(BioBearError::from)?;
+
+    Ok(Arc::new(table))
+}
@@ -0,0 +1,141 @@
+// Copyright 2024 WHERE TRUE Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Export results through the Arrow PyCapsule interface, which lets libraries like Polars,
+//! DuckDB and nanoarrow read them without going through pyarrow.
+//!
+//! See <https://arrow.apache.org/docs/format/CDataInterface/PyCapsuleInterface.html>.
+
+use std::{ffi::CString, sync::Arc};
+
+use arrow::{
+    array::RecordBatch,
+    compute::{can_cast_types, cast},
+    datatypes::{Schema, SchemaRef},
+    error::ArrowError,
+    ffi::FFI_ArrowSchema,
+    ffi_stream::FFI_ArrowArrayStream,
+};
+use datafusion::{
+    error::DataFusionError, execution::SendableRecordBatchStream,
+    physical_plan::stream::RecordBatchStreamAdapter,
+};
+use futures::StreamExt;
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyCapsule};
+
+use crate::{
+    error::BioBearError,
+    progress::{ProgressReceiver, ProgressRecordBatchReader},
+    runtime::get_tokio_runtime,
+};
+
+const ARROW_SCHEMA: &str = "arrow_schema";
+const ARROW_ARRAY_STREAM: &str = "arrow_array_stream";
+
+/// Wrap the schema in an `arrow_schema` PyCapsule.
+pub(crate) fn schema_to_capsule<'py>(
+    py: Python<'py>,
+    schema: &Schema,
+) -> PyResult<Bound<'py, PyCapsule>> {
+    let schema = FFI_ArrowSchema::try_from(schema).map_err(BioBearError::from)?;
+
+    PyCapsule::new_bound(py, schema, Some(capsule_name(ARROW_SCHEMA)))
+}
+
+/// Wrap the stream in an `arrow_array_stream` PyCapsule, whose batches are pulled from the
+/// stream on the shared runtime as the consumer reads them, reporting the progress received by
+/// `progress` in between.
+///
+/// If the consumer requests a schema the batches are cast to it, matching columns by name. A
+/// request that can't be satisfied is ignored, as the interface allows, leaving the consumer to
+/// deal with the stream's own schema.
+pub(crate) fn stream_to_capsule<'py>(
+    py: Python<'py>,
+    stream: SendableRecordBatchStream,
+    progress: Option<ProgressReceiver>,
+    requested_schema: Option<&Bound<'py, PyCapsule>>,
+) -> PyResult<Bound<'py, PyCapsule>> {
+    let stream = match requested_schema {
+        Some(capsule) => {
+            let requested = schema_from_capsule(capsule)?;
+            cast_stream(stream, Arc::new(requested))
+        }
+        None => stream,
+    };
+
+    let reader = ProgressRecordBatchReader::new(stream, get_tokio_runtime()?, progress);
+    let stream = FFI_ArrowArrayStream::new(Box::new(reader));
+
+    PyCapsule::new_bound(py, stream, Some(capsule_name(ARROW_ARRAY_STREAM)))
+}
+
+fn capsule_name(name: &str) -> CString {
+    CString::new(name).expect("capsule names don't contain NUL bytes")
+}
+
+/// Read the schema out of an `arrow_schema` PyCapsule, which remains owned by the capsule.
+fn schema_from_capsule(capsule: &Bound<'_, PyCapsule>) -> PyResult<Schema> {
+    let name = capsule.name()?.map(|name| name.to_string_lossy());
+
+    if name.as_deref() != Some(ARROW_SCHEMA) {
+        return Err(PyValueError::new_err(format!(
+            "Expected an {ARROW_SCHEMA} PyCapsule, got {}",
+            name.as_deref().unwrap_or("an unnamed capsule")
+        )));
+    }
+
+    // SAFETY: the capsule's name guarantees it holds an FFI_ArrowSchema.
+    let schema = unsafe { capsule.reference::<FFI_ArrowSchema>() };
+
+    Ok(Schema::try_from(schema).map_err(BioBearError::from)?)
+}
+
+/// Cast the stream's batches to the requested schema, or return the stream unchanged if a
+/// requested column is missing or can't be cast.
+fn cast_stream(
+    stream: SendableRecordBatchStream,
+    requested: SchemaRef,
+) -> SendableRecordBatchStream {
+    let schema = stream.schema();
+    if schema == requested {
+        return stream;
+    }
+
+    let Some(indices) = requested
+        .fields()
+        .iter()
+        .map(|field| {
+            let (index, source) = schema.column_with_name(field.name())?;
+            can_cast_types(source.data_type(), field.data_type()).then_some(index)
+        })
+        .collect::<Option<Vec<_>>>()
+    else {
+        return stream;
+    };
+
+    let target = requested.clone();
+    let cast_batch = move |batch: RecordBatch| -> Result<RecordBatch, ArrowError> {
+        let columns = indices
+            .iter()
+            .zip(target.fields())
+            .map(|(&index, field)| cast(batch.column(index), field.data_type()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        RecordBatch::try_new(target.clone(), columns)
+    };
+
+    let stream = stream.map(move |batch| Ok::<_, DataFusionError>(cast_batch(batch?)?));
+
+    Box::pin(RecordBatchStreamAdapter::new(requested, stream))
+}
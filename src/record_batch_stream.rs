@@ -0,0 +1,268 @@
+// Copyright 2024 WHERE TRUE Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use arrow::{array::RecordBatch, pyarrow::ToPyArrow};
+use datafusion::{
+    error::DataFusionError, execution::SendableRecordBatchStream, physical_plan::execute_stream,
+    prelude::DataFrame,
+};
+use futures::StreamExt;
+use pyo3::{exceptions::PyStopAsyncIteration, prelude::*};
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    progress::{create_physical_plan, ProgressHook, SharedReporter},
+    runtime::{cancellable_future_into_py, wait_for_cancellable_future_with_ticks},
+};
+
+/// An async iterator over the record batches of a query, for use with `async for`.
+///
+/// The query starts executing on the first call to `__anext__`, and its progress is reported to
+/// the result's progress hook while the batches are awaited.
+#[pyclass(name = "RecordBatchStream")]
+pub(crate) struct RecordBatchStream {
+    state: Arc<Mutex<IteratorState>>,
+    reporter: SharedReporter,
+    cancellation: CancellationToken,
+}
+
+impl RecordBatchStream {
+    pub(crate) fn new(
+        df: Arc<DataFrame>,
+        progress: Option<Arc<ProgressHook>>,
+        cancellation: CancellationToken,
+    ) -> Self {
+        let reporter = SharedReporter::new(1);
+
+        Self {
+            state: Arc::new(Mutex::new(IteratorState::new(
+                df,
+                progress,
+                reporter.clone(),
+                None,
+            ))),
+            reporter,
+            cancellation,
+        }
+    }
+}
+
+#[pymethods]
+impl RecordBatchStream {
+    fn __aiter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// Return an awaitable that resolves to the next pyarrow RecordBatch.
+    fn __anext__(&self, py: Python) -> PyResult<PyObject> {
+        let state = self.state.clone();
+        let tick_reporter = self.reporter.clone();
+        let reporter = self.reporter.clone();
+
+        cancellable_future_into_py(
+            py,
+            async move { state.lock().await.next(None).await },
+            &self.cancellation,
+            move |py| tick_reporter.report(py),
+            move |py, batch| {
+                reporter.report(py)?;
+
+                match batch {
+                    Some(batch) => batch.to_pyarrow(py),
+                    None => Err(PyStopAsyncIteration::new_err(())),
+                }
+            },
+        )
+    }
+}
+
+/// The state of a [`RecordBatchIterator`] or [`RecordBatchStream`], shared with the task reading
+/// the next batch.
+struct IteratorState {
+    df: Arc<DataFrame>,
+    progress: Option<Arc<ProgressHook>>,
+    reporter: SharedReporter,
+    stream: Option<SendableRecordBatchStream>,
+    /// The rows of the last batch read that haven't been returned yet.
+    pending: Option<RecordBatch>,
+    finished: bool,
+}
+
+impl IteratorState {
+    fn new(
+        df: Arc<DataFrame>,
+        progress: Option<Arc<ProgressHook>>,
+        reporter: SharedReporter,
+        stream: Option<SendableRecordBatchStream>,
+    ) -> Self {
+        Self {
+            df,
+            progress,
+            reporter,
+            stream,
+            pending: None,
+            finished: false,
+        }
+    }
+
+    /// Read the next batch, splitting batches larger than `max_rows`.
+    async fn next(
+        &mut self,
+        max_rows: Option<usize>,
+    ) -> Result<Option<RecordBatch>, DataFusionError> {
+        let batch = match self.pending.take() {
+            Some(batch) => batch,
+            None if self.finished => return Ok(None),
+            None => {
+                let stream = match self.stream.as_mut() {
+                    Some(stream) => stream,
+                    None => {
+                        let df = self.df.as_ref().clone();
+                        let (plan, task_ctx, reporter) =
+                            create_physical_plan(df, self.progress.clone()).await?;
+                        self.reporter.set(reporter);
+
+                        self.stream.insert(execute_stream(plan, task_ctx)?)
+                    }
+                };
+
+                match stream.next().await.transpose()? {
+                    Some(batch) => batch,
+                    None => {
+                        self.finished = true;
+                        self.reporter.finish_stream();
+
+                        return Ok(None);
+                    }
+                }
+            }
+        };
+
+        match max_rows {
+            Some(max_rows) if batch.num_rows() > max_rows => {
+                self.pending = Some(batch.slice(max_rows, batch.num_rows() - max_rows));
+                Ok(Some(batch.slice(0, max_rows)))
+            }
+            _ => Ok(Some(batch)),
+        }
+    }
+}
+
+/// An iterator over the record batches of a query, for use with `for`.
+///
+/// Batches are read from the query as they're requested, so only the batches being produced
+/// are held in memory. The query starts executing on the first call to `__next__`, unless the
+/// iterator reads a partition that's already executing. Its progress is reported to the
+/// result's progress hook while the batches are waited for.
+#[pyclass(name = "RecordBatchIterator")]
+pub(crate) struct RecordBatchIterator {
+    state: Arc<Mutex<IteratorState>>,
+    reporter: SharedReporter,
+    max_rows: Option<usize>,
+    cancellation: CancellationToken,
+}
+
+impl RecordBatchIterator {
+    /// Iterate over the batches of the query, at most `max_rows` rows at a time.
+    pub(crate) fn new(
+        df: Arc<DataFrame>,
+        progress: Option<Arc<ProgressHook>>,
+        max_rows: Option<usize>,
+        cancellation: CancellationToken,
+    ) -> Self {
+        let reporter = SharedReporter::new(1);
+        let state = IteratorState::new(df, progress, reporter.clone(), None);
+
+        Self::with_state(state, reporter, max_rows, cancellation)
+    }
+
+    /// Iterate over the batches of an executing stream, e.g. one partition of the query, whose
+    /// progress is reported with the query's other streams by `reporter`.
+    pub(crate) fn from_stream(
+        df: Arc<DataFrame>,
+        stream: SendableRecordBatchStream,
+        reporter: SharedReporter,
+        max_rows: Option<usize>,
+        cancellation: CancellationToken,
+    ) -> Self {
+        let state = IteratorState::new(df, None, reporter.clone(), Some(stream));
+
+        Self::with_state(state, reporter, max_rows, cancellation)
+    }
+
+    fn with_state(
+        state: IteratorState,
+        reporter: SharedReporter,
+        max_rows: Option<usize>,
+        cancellation: CancellationToken,
+    ) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(state)),
+            reporter,
+            max_rows,
+            cancellation,
+        }
+    }
+}
+
+#[pymethods]
+impl RecordBatchIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// Return the next pyarrow RecordBatch, waiting for the query to produce it.
+    fn __next__(&self, py: Python) -> PyResult<Option<PyObject>> {
+        let state = self.state.clone();
+        let max_rows = self.max_rows;
+
+        let batch = wait_for_cancellable_future_with_ticks(
+            py,
+            async move { state.lock().await.next(max_rows).await },
+            &self.cancellation,
+            &mut |py| self.reporter.report(py),
+        )?;
+
+        self.reporter.report(py)?;
+
+        batch.map(|batch| batch.to_pyarrow(py)).transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::prelude::SessionContext;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_iterator_state_splits_batches() -> Result<(), DataFusionError> {
+        let ctx = SessionContext::new();
+        let df = ctx.sql("SELECT * FROM unnest(range(10)) AS t(x)").await?;
+
+        let mut state = IteratorState::new(Arc::new(df), None, SharedReporter::new(1), None);
+
+        let mut sizes = Vec::new();
+        while let Some(batch) = state.next(Some(4)).await? {
+            sizes.push(batch.num_rows());
+        }
+
+        assert_eq!(sizes, vec![4, 4, 2]);
+
+        Ok(())
+    }
+}
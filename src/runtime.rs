@@ -12,25 +12,526 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::future::Future;
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc, Mutex, PoisonError,
+    },
+    time::Duration,
+};
 
-use pyo3::{prelude::*, PyRef, Python};
-use tokio::runtime::Runtime;
+use pyo3::{
+    prelude::*,
+    types::{PyCFunction, PyType},
+    Python,
+};
+use tokio::{
+    runtime::{Builder, Runtime},
+    task::AbortHandle,
+};
+use tokio_util::sync::CancellationToken;
 
-#[pyclass]
-pub(crate) struct TokioRuntime(pub(crate) tokio::runtime::Runtime);
+use crate::error::{BioBearError, BioBearResult};
 
-/// Get the Tokio Runtime from Python
-pub(crate) fn get_tokio_runtime(py: Python) -> PyRef<TokioRuntime> {
-    let exon = py.import_bound("biobear").unwrap();
-    exon.getattr("__runtime").unwrap().extract().unwrap()
+/// The environment variable setting the number of worker threads of the Tokio runtime.
+const WORKER_THREADS_ENV: &str = "BIOBEAR_WORKER_THREADS";
+
+/// The environment variable setting the maximum number of blocking threads of the Tokio runtime.
+const MAX_BLOCKING_THREADS_ENV: &str = "BIOBEAR_MAX_BLOCKING_THREADS";
+
+/// Options for building the Tokio runtime, where unset options use Tokio's defaults.
+#[derive(Debug, Default, Clone, Copy)]
+struct RuntimeOptions {
+    worker_threads: Option<usize>,
+    max_blocking_threads: Option<usize>,
+}
+
+impl RuntimeOptions {
+    /// Read the options from the environment.
+    fn from_env() -> BioBearResult<Self> {
+        Ok(Self {
+            worker_threads: thread_count_from_env(WORKER_THREADS_ENV)?,
+            max_blocking_threads: thread_count_from_env(MAX_BLOCKING_THREADS_ENV)?,
+        })
+    }
+
+    /// Use the options set in `self`, falling back to those in `other`.
+    fn or(self, other: Self) -> Self {
+        Self {
+            worker_threads: self.worker_threads.or(other.worker_threads),
+            max_blocking_threads: self.max_blocking_threads.or(other.max_blocking_threads),
+        }
+    }
+
+    fn build(&self) -> BioBearResult<Runtime> {
+        let mut builder = Builder::new_multi_thread();
+
+        builder
+            .thread_name_fn(move || {
+                static THREAD_ID: AtomicU64 = AtomicU64::new(0);
+                let id = THREAD_ID.fetch_add(1, Ordering::Relaxed);
+                format!("biobear-python-thread-{}", id)
+            })
+            .enable_all();
+
+        if let Some(worker_threads) = self.worker_threads {
+            builder.worker_threads(worker_threads);
+        }
+
+        if let Some(max_blocking_threads) = self.max_blocking_threads {
+            builder.max_blocking_threads(max_blocking_threads);
+        }
+
+        Ok(builder.build()?)
+    }
+}
+
+/// Parse a positive thread count from the environment variable, if it's set.
+fn thread_count_from_env(name: &str) -> BioBearResult<Option<usize>> {
+    let Ok(value) = std::env::var(name) else {
+        return Ok(None);
+    };
+
+    match value.trim().parse::<usize>() {
+        Ok(count) if count > 0 => Ok(Some(count)),
+        _ => Err(BioBearError::InvalidConfiguration(format!(
+            "{name} must be a positive integer, got {value:?}"
+        ))),
+    }
+}
+
+/// The options set with `configure_runtime`, which take precedence over the environment.
+static RUNTIME_OPTIONS: Mutex<RuntimeOptions> = Mutex::new(RuntimeOptions {
+    worker_threads: None,
+    max_blocking_threads: None,
+});
+
+/// A Tokio runtime, along with the process that built it.
+struct ProcessRuntime {
+    pid: u32,
+    runtime: Arc<Runtime>,
+}
+
+/// The Tokio runtime shared by every query and reader, built on first use.
+static RUNTIME: Mutex<Option<ProcessRuntime>> = Mutex::new(None);
+
+/// Get the shared Tokio runtime, building it if this is the first use in this process.
+///
+/// A forked child inherits the parent's runtime without its threads, so queries run on it would
+/// never complete. The child builds its own runtime instead, and leaks the inherited one, as
+/// dropping it waits for the missing threads to stop.
+pub(crate) fn get_tokio_runtime() -> BioBearResult<Arc<Runtime>> {
+    let mut runtime = RUNTIME.lock().unwrap_or_else(PoisonError::into_inner);
+    let pid = std::process::id();
+
+    match runtime.take() {
+        Some(current) if current.pid == pid => {
+            let shared = current.runtime.clone();
+            *runtime = Some(current);
+
+            return Ok(shared);
+        }
+        Some(inherited) => {
+            std::mem::forget(inherited.runtime);
+
+            // Any deliveries in progress at the fork belong to the parent's tasks.
+            DELIVERING.store(0, Ordering::SeqCst);
+        }
+        None => {}
+    }
+
+    let options = *RUNTIME_OPTIONS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+
+    let new_runtime = Arc::new(options.or(RuntimeOptions::from_env()?).build()?);
+    *runtime = Some(ProcessRuntime {
+        pid,
+        runtime: new_runtime.clone(),
+    });
+
+    Ok(new_runtime)
+}
+
+/// Whether the shared runtime was built by this process, rather than inherited from a parent.
+fn runtime_started_in_process() -> bool {
+    RUNTIME
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .as_ref()
+        .is_some_and(|runtime| runtime.pid == std::process::id())
 }
 
+/// Configure the Tokio runtime that executes queries, overriding the `BIOBEAR_WORKER_THREADS`
+/// and `BIOBEAR_MAX_BLOCKING_THREADS` environment variables.
+///
+/// The runtime is built the first time it's used, so this must be called before running any
+/// queries or opening any readers.
+#[pyfunction]
+#[pyo3(signature = (*, worker_threads=None, max_blocking_threads=None))]
+pub(crate) fn configure_runtime(
+    worker_threads: Option<usize>,
+    max_blocking_threads: Option<usize>,
+) -> PyResult<()> {
+    if worker_threads == Some(0) || max_blocking_threads == Some(0) {
+        return Err(BioBearError::InvalidConfiguration(
+            "Thread counts must be greater than 0".to_string(),
+        )
+        .into());
+    }
+
+    if runtime_started_in_process() {
+        return Err(BioBearError::InvalidConfiguration(
+            "The runtime has already started, configure it before running any queries".to_string(),
+        )
+        .into());
+    }
+
+    *RUNTIME_OPTIONS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner) = RuntimeOptions {
+        worker_threads,
+        max_blocking_threads,
+    };
+
+    Ok(())
+}
+
+/// How often to check for pending Python signals, e.g. Ctrl-C, while waiting on a future.
+const SIGNAL_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
 /// Utility to collect rust futures with GIL released
-pub fn wait_for_future<F: Future + Send>(py: Python, f: F) -> F::Output
+///
+/// Pending signals are checked whenever the future yields, so Ctrl-C drops the future and raises
+/// `KeyboardInterrupt`. Use [`wait_for_cancellable_future`] to run a query, as CPU-bound
+/// execution may not yield for a long time.
+pub fn wait_for_future<F, T, E>(py: Python, f: F) -> PyResult<T>
+where
+    F: Future<Output = Result<T, E>> + Send,
+    T: Send,
+    E: Send,
+    BioBearError: From<E>,
+{
+    let runtime = get_tokio_runtime()?;
+
+    let result = py.allow_threads(|| {
+        runtime.block_on(async {
+            tokio::select! {
+                result = f => Ok(result),
+                err = check_signals() => Err(err),
+            }
+        })
+    });
+
+    Ok(result?.map_err(BioBearError::from)?)
+}
+
+/// Run the future on the Tokio runtime and wait for it with the GIL released.
+///
+/// Ctrl-C raises `KeyboardInterrupt`, and cancelling the token (e.g. from another thread) raises
+/// `concurrent.futures.CancelledError`. Either way the task is aborted, which drops the query's
+/// streams the next time they yield.
+pub(crate) fn wait_for_cancellable_future<F, T, E>(
+    py: Python,
+    f: F,
+    token: &CancellationToken,
+) -> PyResult<T>
+where
+    F: Future<Output = Result<T, E>> + Send + 'static,
+    T: Send + 'static,
+    E: Send + 'static,
+    BioBearError: From<E>,
+{
+    wait_for_cancellable_future_with_ticks(py, f, token, &mut |_| Ok(()))
+}
+
+/// Like [`wait_for_cancellable_future`], but also calls `on_tick` with the GIL held after each
+/// signal check, e.g. to report progress. An error from `on_tick` aborts the task and is raised.
+pub(crate) fn wait_for_cancellable_future_with_ticks<F, T, E>(
+    py: Python,
+    f: F,
+    token: &CancellationToken,
+    on_tick: &mut (dyn FnMut(Python) -> PyResult<()> + Send),
+) -> PyResult<T>
+where
+    F: Future<Output = Result<T, E>> + Send + 'static,
+    T: Send + 'static,
+    E: Send + 'static,
+    BioBearError: From<E>,
+{
+    let (tx, rx) = mpsc::channel();
+
+    let handle = get_tokio_runtime()?.spawn(async move {
+        // The receiver is gone if the caller was interrupted, in which case the task is aborted.
+        let _ = tx.send(f.await.map_err(BioBearError::from));
+    });
+
+    // Wait on the channel rather than the runtime, so the signal checks don't rely on a free
+    // Tokio worker to drive timers while the query is running.
+    let result = py.allow_threads(move || {
+        wait_on_channel(rx, token, || {
+            Python::with_gil(|py| {
+                py.check_signals()?;
+                on_tick(py)
+            })
+        })
+    });
+
+    match result {
+        Ok(result) => Ok(result?),
+        Err(interrupted) => {
+            handle.abort();
+            Err(interrupted.unwrap_or_else(|| cancelled_error(py)))
+        }
+    }
+}
+
+/// Wait for a task to send its result on the channel, calling `on_tick` after each signal check
+/// interval. Waiting stops early with the error from `on_tick`, or with `None` if the token is
+/// cancelled.
+fn wait_on_channel<T>(
+    rx: mpsc::Receiver<BioBearResult<T>>,
+    token: &CancellationToken,
+    mut on_tick: impl FnMut() -> PyResult<()>,
+) -> Result<BioBearResult<T>, Option<PyErr>> {
+    loop {
+        match rx.recv_timeout(SIGNAL_CHECK_INTERVAL) {
+            Ok(result) => return Ok(result),
+            Err(RecvTimeoutError::Timeout) => {
+                on_tick().map_err(Some)?;
+
+                if token.is_cancelled() {
+                    return Err(None);
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                return Ok(Err(BioBearError::new("Query task panicked")))
+            }
+        }
+    }
+}
+
+/// Periodically run Python's signal handlers, resolving to the error raised by one of them.
+async fn check_signals() -> PyErr {
+    let mut interval = tokio::time::interval(SIGNAL_CHECK_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if let Err(err) = Python::with_gil(|py| py.check_signals()) {
+            return err;
+        }
+    }
+}
+
+/// Create a `concurrent.futures.CancelledError` for a query cancelled from another thread.
+fn cancelled_error(py: Python) -> PyErr {
+    let error_type = py
+        .import_bound("concurrent.futures")
+        .and_then(|module| module.getattr("CancelledError"))
+        .and_then(|error_type| Ok(error_type.downcast_into::<PyType>()?));
+
+    match error_type {
+        Ok(error_type) => PyErr::from_type_bound(error_type, "Query was cancelled"),
+        Err(err) => err,
+    }
+}
+
+/// Set once the interpreter starts shutting down, after which spawned tasks no longer take the GIL.
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+
+/// The number of spawned tasks currently holding or waiting for the GIL.
+static DELIVERING: AtomicUsize = AtomicUsize::new(0);
+
+/// Run `f` with the GIL held from a spawned task, unless the interpreter is shutting down.
+fn with_gil_from_task<R>(f: impl FnOnce(Python) -> R) -> Option<R> {
+    DELIVERING.fetch_add(1, Ordering::SeqCst);
+    let result = (!SHUTTING_DOWN.load(Ordering::SeqCst)).then(|| Python::with_gil(f));
+    DELIVERING.fetch_sub(1, Ordering::SeqCst);
+
+    result
+}
+
+/// Wait for spawned tasks to finish delivering their results, and stop any further deliveries.
+///
+/// Registered with `atexit`, as a Tokio thread acquiring the GIL during interpreter finalization
+/// aborts the process.
+#[pyfunction]
+pub(crate) fn shutdown_async_tasks(py: Python) {
+    SHUTTING_DOWN.store(true, Ordering::SeqCst);
+
+    // A forked child that never ran a query has no tasks of its own to wait for.
+    if !runtime_started_in_process() {
+        return;
+    }
+
+    py.allow_threads(|| {
+        while DELIVERING.load(Ordering::SeqCst) > 0 {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    });
+}
+
+/// Spawn the future on the Tokio runtime and return an asyncio Future that resolves to its output.
+///
+/// Must be called from a coroutine, as the result is delivered to the running event loop. The
+/// output is converted with `to_python`, which is the only point the Tokio task takes the GIL.
+/// Cancelling the asyncio Future aborts the Tokio task.
+pub(crate) fn future_into_py<F, T, C>(py: Python, f: F, to_python: C) -> PyResult<PyObject>
+where
+    F: Future<Output = PyResult<T>> + Send + 'static,
+    T: Send + 'static,
+    C: FnOnce(Python, T) -> PyResult<PyObject> + Send + 'static,
+{
+    let (event_loop, py_future) = create_py_future(py)?;
+    let result_future = py_future.clone_ref(py);
+
+    let handle = get_tokio_runtime()?.spawn(async move {
+        let result = f.await;
+        deliver_from_task(event_loop, result_future, result, to_python);
+    });
+
+    abort_on_cancel(py, &py_future, handle.abort_handle())?;
+
+    Ok(py_future)
+}
+
+/// Like [`future_into_py`], but the asyncio Future raises `concurrent.futures.CancelledError` if
+/// the token is cancelled, e.g. by `ExecutionResult.cancel()`, and `on_tick` is called with the GIL
+/// held at each signal check interval while the future runs, e.g. to report progress. An error
+/// from `on_tick` aborts the task and is raised.
+pub(crate) fn cancellable_future_into_py<F, T, E, K, C>(
+    py: Python,
+    f: F,
+    token: &CancellationToken,
+    mut on_tick: K,
+    to_python: C,
+) -> PyResult<PyObject>
+where
+    F: Future<Output = Result<T, E>> + Send + 'static,
+    T: Send + 'static,
+    E: Send + 'static,
+    BioBearError: From<E>,
+    K: FnMut(Python) -> PyResult<()> + Send + 'static,
+    C: FnOnce(Python, T) -> PyResult<PyObject> + Send + 'static,
+{
+    let runtime = get_tokio_runtime()?;
+    let (event_loop, py_future) = create_py_future(py)?;
+    let (tx, rx) = mpsc::channel();
+
+    let handle = runtime.spawn(async move {
+        let _ = tx.send(f.await.map_err(BioBearError::from));
+    });
+
+    abort_on_cancel(py, &py_future, handle.abort_handle())?;
+
+    let result_future = py_future.clone_ref(py);
+    let token = token.clone();
+
+    // Wait on a blocking thread rather than a Tokio worker, for the same reason as
+    // `wait_for_cancellable_future_with_ticks` waits on the channel.
+    runtime.spawn_blocking(move || {
+        let ticks = || with_gil_from_task(&mut on_tick).unwrap_or(Ok(()));
+
+        let result = match wait_on_channel(rx, &token, ticks) {
+            Ok(result) => result.map(Some).map_err(PyErr::from),
+            Err(interrupted) => {
+                handle.abort();
+                interrupted.map_or(Ok(None), Err)
+            }
+        };
+
+        deliver_from_task(event_loop, result_future, result, |py, value| match value {
+            Some(value) => to_python(py, value),
+            None => Err(cancelled_error(py)),
+        });
+    });
+
+    Ok(py_future)
+}
+
+/// Create an asyncio Future on the running event loop, returning the loop and the Future.
+fn create_py_future(py: Python) -> PyResult<(PyObject, PyObject)> {
+    let event_loop = py
+        .import_bound("asyncio")?
+        .call_method0("get_running_loop")?;
+    let py_future = event_loop.call_method0("create_future")?;
+
+    Ok((event_loop.unbind(), py_future.unbind()))
+}
+
+/// Abort the task when the asyncio Future is cancelled.
+fn abort_on_cancel(py: Python, py_future: &PyObject, abort_handle: AbortHandle) -> PyResult<()> {
+    let abort_on_cancel = PyCFunction::new_closure_bound(py, None, None, move |args, _kwargs| {
+        let future = args.get_item(0)?;
+        if future.call_method0("cancelled")?.extract::<bool>()? {
+            abort_handle.abort();
+        }
+
+        Ok::<_, PyErr>(())
+    })?;
+    py_future.call_method1(py, "add_done_callback", (abort_on_cancel,))?;
+
+    Ok(())
+}
+
+/// Deliver a task's result to the asyncio Future, unless the interpreter is shutting down.
+fn deliver_from_task<T, C>(
+    event_loop: PyObject,
+    future: PyObject,
+    result: PyResult<T>,
+    to_python: C,
+) where
+    C: FnOnce(Python, T) -> PyResult<PyObject>,
+{
+    with_gil_from_task(|py| {
+        if let Err(err) = deliver_result(py, event_loop, future, result, to_python) {
+            err.write_unraisable_bound(py, None);
+        }
+    });
+}
+
+/// Schedule setting the result (or exception) on the asyncio Future from the event loop's thread.
+fn deliver_result<T, C>(
+    py: Python,
+    event_loop: PyObject,
+    future: PyObject,
+    result: PyResult<T>,
+    to_python: C,
+) -> PyResult<()>
 where
-    F::Output: Send,
+    C: FnOnce(Python, T) -> PyResult<PyObject>,
 {
-    let runtime: &Runtime = &get_tokio_runtime(py).0;
-    py.allow_threads(|| runtime.block_on(f))
+    // Nothing is left to await the result if the loop was closed while the task was running.
+    if event_loop
+        .call_method0(py, "is_closed")?
+        .extract::<bool>(py)?
+    {
+        return Ok(());
+    }
+
+    let result = result.and_then(|value| to_python(py, value));
+
+    let set_result = PyCFunction::new_closure_bound(py, None, None, move |args, _kwargs| {
+        let py = args.py();
+        let future = future.bind(py);
+
+        // The awaiting coroutine may have been cancelled in the meantime.
+        if future.call_method0("done")?.extract::<bool>()? {
+            return Ok(());
+        }
+
+        match &result {
+            Ok(value) => future.call_method1("set_result", (value,))?,
+            Err(err) => future.call_method1("set_exception", (err.value_bound(py),))?,
+        };
+
+        Ok::<_, PyErr>(())
+    })?;
+
+    event_loop.call_method1(py, "call_soon_threadsafe", (set_result,))?;
+
+    Ok(())
 }
@@ -0,0 +1,194 @@
+// Copyright 2024 WHERE TRUE Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use datafusion::sql::{
+    parser::DFParser,
+    sqlparser::{
+        dialect::dialect_from_str,
+        parser::{Parser, ParserError},
+        tokenizer::{Location, Token},
+    },
+};
+
+use crate::error::BioBearError;
+
+/// A statement of a SQL script, along with where it starts in the script.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ScriptStatement {
+    pub(crate) text: String,
+    pub(crate) line: u64,
+    pub(crate) column: u64,
+}
+
+/// An error parsing a script, along with where the parser stopped.
+#[derive(Debug)]
+pub(crate) struct ScriptParseError {
+    pub(crate) error: ParserError,
+    pub(crate) location: Location,
+}
+
+/// Split the script into its statements, parsing them the way DataFusion does.
+///
+/// The statements keep their source text, so they can be run with Exon's SQL extensions.
+pub(crate) fn parse_script(
+    script: &str,
+    dialect: &str,
+) -> Result<Vec<ScriptStatement>, ScriptParseError> {
+    let dialect = dialect_from_str(dialect).ok_or_else(|| ScriptParseError {
+        error: ParserError::ParserError(format!("Unsupported SQL dialect: {dialect}")),
+        location: Location { line: 0, column: 0 },
+    })?;
+
+    // `DFParser::new_with_dialect` drops the token locations, so tokenize with the SQL parser.
+    let parser = Parser::new(dialect.as_ref())
+        .try_with_sql(script)
+        .map_err(|error| ScriptParseError {
+            error,
+            location: Location { line: 0, column: 0 },
+        })?;
+    let mut parser = DFParser { parser };
+
+    let mut statements = Vec::new();
+    let mut expecting_statement_delimiter = false;
+
+    loop {
+        while parser.parser.consume_token(&Token::SemiColon) {
+            expecting_statement_delimiter = false;
+        }
+
+        let start = parser.parser.peek_token();
+        if start.token == Token::EOF {
+            return Ok(statements);
+        }
+
+        let result = if expecting_statement_delimiter {
+            Err(ParserError::ParserError(format!(
+                "Expected end of statement, found: {start}"
+            )))
+        } else {
+            parser.parse_statement()
+        };
+
+        if let Err(error) = result {
+            return Err(ScriptParseError {
+                error,
+                location: parser.parser.peek_token().location,
+            });
+        }
+
+        // DataFusion's CREATE EXTERNAL TABLE and COPY statements consume their delimiter.
+        parser.parser.prev_token();
+        let last = parser.parser.next_token();
+
+        let end = if last.token == Token::SemiColon {
+            expecting_statement_delimiter = false;
+            last.location
+        } else {
+            expecting_statement_delimiter = true;
+            parser.parser.peek_token().location
+        };
+
+        let text = &script[byte_offset(script, start.location)..byte_offset(script, end)];
+
+        statements.push(ScriptStatement {
+            text: text.trim_end().to_string(),
+            line: start.location.line,
+            column: start.location.column,
+        });
+    }
+}
+
+impl From<ScriptParseError> for BioBearError {
+    fn from(value: ScriptParseError) -> Self {
+        let message = value.error.to_string();
+
+        // The SQL parser includes the location in its messages, but DataFusion's doesn't.
+        if message.contains(" at Line: ") {
+            Self::SQLSyntaxError(message)
+        } else {
+            Self::SQLSyntaxError(format!("{message}{}", value.location))
+        }
+    }
+}
+
+/// Convert the parser's 1-based line and character column into a byte offset in the text, where
+/// an unknown location, e.g. that of the end of the input, is the end of the text.
+fn byte_offset(text: &str, location: Location) -> usize {
+    if location.line == 0 || location.column == 0 {
+        return text.len();
+    }
+
+    let mut line_start = 0;
+    for _ in 1..location.line {
+        match text[line_start..].find('\n') {
+            Some(newline) => line_start += newline + 1,
+            None => return text.len(),
+        }
+    }
+
+    text[line_start..]
+        .char_indices()
+        .nth(location.column as usize - 1)
+        .map_or(text.len(), |(offset, _)| line_start + offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::BioBearResult;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_script() -> BioBearResult<()> {
+        let script = "-- setup\nCREATE TABLE t AS VALUES (1);\n\nSELECT *\nFROM t;;\nCOPY t TO 'x.csv';\nSELECT 'é'";
+
+        let statements = parse_script(script, "generic")?;
+
+        assert_eq!(
+            statements,
+            vec![
+                ScriptStatement {
+                    text: "CREATE TABLE t AS VALUES (1)".to_string(),
+                    line: 2,
+                    column: 1,
+                },
+                ScriptStatement {
+                    text: "SELECT *\nFROM t".to_string(),
+                    line: 4,
+                    column: 1,
+                },
+                ScriptStatement {
+                    text: "COPY t TO 'x.csv'".to_string(),
+                    line: 6,
+                    column: 1,
+                },
+                ScriptStatement {
+                    text: "SELECT 'é'".to_string(),
+                    line: 7,
+                    column: 1,
+                },
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_script_error_location() {
+        let err = parse_script("SELECT 1;\nSELECT 2\nFORM t", "generic").unwrap_err();
+
+        assert_eq!(err.location.line, 3);
+        assert_eq!(err.location.column, 6);
+    }
+}
@@ -0,0 +1,284 @@
+// Copyright 2024 WHERE TRUE Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use datafusion::execution::runtime_env::{RuntimeEnv, RuntimeEnvBuilder};
+use datafusion::prelude::SessionConfig;
+use exon::new_exon_config;
+use pyo3::{pyclass, pymethods, PyResult};
+
+use crate::error::{BioBearError, BioBearResult};
+
+/// The prefix of the Exon config extension's options, which DataFusion lists without it.
+const EXON_CONFIG_PREFIX: &str = "exon";
+
+/// List the config's options by their full key, e.g. `datafusion.execution.batch_size` or
+/// `exon.vcf_parse_info`. Options without a value, such as extension options left at their
+/// default, map to `None`.
+pub(crate) fn config_entries(config: &SessionConfig) -> Vec<(String, Option<String>)> {
+    config
+        .options()
+        .entries()
+        .into_iter()
+        .map(|entry| {
+            let key = if entry.key.starts_with("datafusion.") {
+                entry.key
+            } else {
+                format!("{EXON_CONFIG_PREFIX}.{}", entry.key)
+            };
+
+            (key, entry.value)
+        })
+        .collect()
+}
+
+/// Set the option with the given full key, validating the key and value.
+pub(crate) fn set_config_option(
+    config: &mut SessionConfig,
+    key: &str,
+    value: &str,
+) -> BioBearResult<()> {
+    config
+        .options_mut()
+        .set(key, value)
+        .map_err(|e| BioBearError::InvalidConfiguration(e.to_string()))
+}
+
+/// Options used to construct a new session.
+///
+/// When using from Python, the arguments are optional, but if passed, must be passed as kwargs.
+///
+/// ```python
+/// from biobear import SessionConfig, new_session
+///
+/// config = SessionConfig(batch_size=4096, memory_limit=8 * 1024**3, spill_path="/scratch")
+/// session = new_session(config)
+/// ```
+#[pyclass(name = "SessionConfig")]
+#[derive(Debug, Clone, Default)]
+pub struct BioBearSessionConfig {
+    /// The number of rows in each record batch.
+    batch_size: Option<usize>,
+    /// The number of partitions used for query execution.
+    target_partitions: Option<usize>,
+    /// The maximum number of bytes the memory pool may allocate.
+    memory_limit: Option<usize>,
+    /// The fraction of the memory limit that may be used, defaults to 1.0.
+    memory_fraction: Option<f64>,
+    /// The directory used for spilling to disk.
+    spill_path: Option<String>,
+    /// True if joins should be repartitioned.
+    repartition_joins: Option<bool>,
+    /// True if aggregations should be repartitioned.
+    repartition_aggregations: Option<bool>,
+    /// True if window functions should be repartitioned.
+    repartition_windows: Option<bool>,
+    /// True if file scans should be repartitioned.
+    repartition_file_scans: Option<bool>,
+}
+
+impl BioBearSessionConfig {
+    /// Build the [`SessionConfig`] starting from the exon defaults.
+    pub(crate) fn session_config(&self) -> SessionConfig {
+        let mut config = new_exon_config();
+
+        if let Some(batch_size) = self.batch_size {
+            config = config.with_batch_size(batch_size);
+        }
+
+        if let Some(target_partitions) = self.target_partitions {
+            config = config.with_target_partitions(target_partitions);
+        }
+
+        if let Some(repartition_joins) = self.repartition_joins {
+            config = config.with_repartition_joins(repartition_joins);
+        }
+
+        if let Some(repartition_aggregations) = self.repartition_aggregations {
+            config = config.with_repartition_aggregations(repartition_aggregations);
+        }
+
+        if let Some(repartition_windows) = self.repartition_windows {
+            config = config.with_repartition_windows(repartition_windows);
+        }
+
+        if let Some(repartition_file_scans) = self.repartition_file_scans {
+            config = config.with_repartition_file_scans(repartition_file_scans);
+        }
+
+        config
+    }
+
+    /// Build the [`RuntimeEnv`] with the memory pool and spill directory.
+    pub(crate) fn runtime_env(&self) -> BioBearResult<Arc<RuntimeEnv>> {
+        let mut builder = RuntimeEnvBuilder::new();
+
+        if let Some(memory_limit) = self.memory_limit {
+            let memory_fraction = self.memory_fraction.unwrap_or(1.0);
+            builder = builder.with_memory_limit(memory_limit, memory_fraction);
+        }
+
+        if let Some(spill_path) = &self.spill_path {
+            if !std::path::Path::new(spill_path).is_dir() {
+                return Err(BioBearError::InvalidConfiguration(format!(
+                    "Spill path does not exist or is not a directory: {spill_path}"
+                )));
+            }
+
+            builder = builder.with_temp_file_path(spill_path);
+        }
+
+        let runtime = builder.build_arc()?;
+
+        Ok(runtime)
+    }
+}
+
+#[pymethods]
+impl BioBearSessionConfig {
+    #[new]
+    #[pyo3(signature = (*, batch_size = None, target_partitions = None, memory_limit = None, memory_fraction = None, spill_path = None, repartition_joins = None, repartition_aggregations = None, repartition_windows = None, repartition_file_scans = None))]
+    #[allow(clippy::too_many_arguments)]
+    fn try_new(
+        batch_size: Option<usize>,
+        target_partitions: Option<usize>,
+        memory_limit: Option<usize>,
+        memory_fraction: Option<f64>,
+        spill_path: Option<String>,
+        repartition_joins: Option<bool>,
+        repartition_aggregations: Option<bool>,
+        repartition_windows: Option<bool>,
+        repartition_file_scans: Option<bool>,
+    ) -> PyResult<Self> {
+        if batch_size == Some(0) {
+            return Err(BioBearError::InvalidConfiguration(
+                "batch_size must be greater than 0".to_string(),
+            )
+            .into());
+        }
+
+        if target_partitions == Some(0) {
+            return Err(BioBearError::InvalidConfiguration(
+                "target_partitions must be greater than 0".to_string(),
+            )
+            .into());
+        }
+
+        if let Some(memory_fraction) = memory_fraction {
+            if memory_fraction <= 0.0 || memory_fraction > 1.0 {
+                return Err(BioBearError::InvalidConfiguration(format!(
+                    "memory_fraction must be in (0, 1], got {memory_fraction}"
+                ))
+                .into());
+            }
+
+            if memory_limit.is_none() {
+                return Err(BioBearError::InvalidConfiguration(
+                    "memory_fraction requires memory_limit to be set".to_string(),
+                )
+                .into());
+            }
+        }
+
+        Ok(Self {
+            batch_size,
+            target_partitions,
+            memory_limit,
+            memory_fraction,
+            spill_path,
+            repartition_joins,
+            repartition_aggregations,
+            repartition_windows,
+            repartition_file_scans,
+        })
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_entries_and_set() -> BioBearResult<()> {
+        let mut config = BioBearSessionConfig::default().session_config();
+
+        set_config_option(&mut config, "datafusion.execution.batch_size", "1024")?;
+        set_config_option(&mut config, "exon.vcf_parse_info", "true")?;
+
+        let entries = config_entries(&config);
+        let value = |key: &str| {
+            entries
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.clone())
+        };
+
+        assert_eq!(
+            value("datafusion.execution.batch_size"),
+            Some(Some("1024".to_string()))
+        );
+        assert_eq!(value("exon.vcf_parse_info"), Some(Some("true".to_string())));
+
+        assert!(matches!(
+            set_config_option(&mut config, "datafusion.execution.batch_size", "many"),
+            Err(BioBearError::InvalidConfiguration(_))
+        ));
+        assert!(set_config_option(&mut config, "datafusion.not_an_option", "1").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_session_config_defaults() {
+        let config = BioBearSessionConfig::default().session_config();
+        let exon_config = new_exon_config();
+
+        assert_eq!(config.batch_size(), exon_config.batch_size());
+        assert_eq!(config.target_partitions(), exon_config.target_partitions());
+    }
+
+    #[test]
+    fn test_session_config_overrides() {
+        let options = BioBearSessionConfig {
+            batch_size: Some(1024),
+            target_partitions: Some(2),
+            repartition_joins: Some(false),
+            ..Default::default()
+        };
+
+        let config = options.session_config();
+
+        assert_eq!(config.batch_size(), 1024);
+        assert_eq!(config.target_partitions(), 2);
+        assert!(!config.repartition_joins());
+    }
+
+    #[test]
+    fn test_runtime_env_missing_spill_path() {
+        let options = BioBearSessionConfig {
+            spill_path: Some("/does/not/exist".to_string()),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            options.runtime_env(),
+            Err(BioBearError::InvalidConfiguration(_))
+        ));
+    }
+}
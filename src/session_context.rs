@@ -12,10 +12,25 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use exon::datasources::bigwig;
-use exon::{ExonRuntimeEnvExt, ExonSession};
-
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use arrow::datatypes::{DataType, Schema};
+use arrow::pyarrow::PyArrowType;
+use datafusion::catalog::SchemaProvider;
+use datafusion::common::ParamValues;
+use datafusion::error::DataFusionError;
+use datafusion::prelude::DataFrame;
+use datafusion::sql::parser::Statement;
+use exon::datasources::{bigwig, ExonFileType};
+use exon::{ExonError, ExonRuntimeEnvExt, ExonSession};
+
+use pyo3::exceptions::PyException;
 use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyString};
+use tokio_util::sync::CancellationToken;
 
 use crate::datasources::bcf::BCFReadOptions;
 use crate::datasources::bigwig::BigWigReadOptions;
@@ -26,29 +41,156 @@ use crate::datasources::mzml::MzMLReadOptions;
 use crate::error;
 use crate::execution_result::ExecutionResult;
 use crate::file_options::FileOptions;
-use crate::runtime::wait_for_future;
+use crate::object_stores::{AzureOptions, GcsOptions, ObjectStoreRegistration, S3Options};
+use crate::params::{bind_param_values, py_to_param_values};
+use crate::prepared_query::PreparedQuery;
+use crate::py_table::table_provider_from_pyobject;
+use crate::runtime::{future_into_py, wait_for_cancellable_future, wait_for_future};
+use crate::script::{parse_script, ScriptStatement};
+use crate::session_config::{config_entries, set_config_option, BioBearSessionConfig};
+use crate::udfs::{
+    aggregate::create_python_udaf, parse_volatility, scalar::create_python_udf,
+    table::PythonTableFunction,
+};
 use pyo3::{pyclass, pymethods};
 
+/// The dialect `ExonSession::sql` parses with, regardless of the session's.
+const EXON_DIALECT: &str = "generic";
+
 #[pyclass]
 pub struct BioBearSessionContext {
     ctx: ExonSession,
 }
 
+impl BioBearSessionContext {
+    /// Plan the SQL query without executing it, binding any parameters, and return its schema.
+    ///
+    /// The statement is parsed and dispatched as `ExonSession::sql` does, so e.g. a COPY to an
+    /// Exon file format plans the same way, but other statements are planned with the session
+    /// state directly, as `ExonSession::sql` runs DDL statements.
+    fn plan_schema(
+        &self,
+        query: &str,
+        params: Option<&Bound<'_, PyAny>>,
+        py: Python,
+    ) -> PyResult<Schema> {
+        let params = params.map(py_to_param_values).transpose()?;
+
+        let dialect = EXON_DIALECT;
+        let state = self.ctx.session.state();
+
+        let statement = state
+            .sql_to_statement(query, dialect)
+            .map_err(|e| planning_error(e, query, dialect))?;
+
+        let df = if is_exon_copy(&statement) {
+            // Exon plans these as a sink for the query, which isn't run until it's collected.
+            wait_for_future(py, async {
+                self.ctx
+                    .sql(query)
+                    .await
+                    .map_err(|e| exon_planning_error(e, query))
+            })?
+        } else {
+            let plan = wait_for_future(py, async {
+                state
+                    .statement_to_plan(statement)
+                    .await
+                    .map_err(|e| planning_error(e, query, dialect))
+            })?;
+
+            DataFrame::new(state, plan)
+        };
+
+        let df = match params {
+            Some(params) => bind_param_values(df, params)?,
+            None => df,
+        };
+
+        Ok(df.schema().into())
+    }
+
+    /// Plan the SQL query and bind any parameters to its placeholders.
+    fn sql_with_params(
+        &self,
+        query: &str,
+        params: Option<&Bound<'_, PyAny>>,
+        py: Python,
+    ) -> PyResult<DataFrame> {
+        let params = params.map(py_to_param_values).transpose()?;
+
+        let df = wait_for_future(py, async {
+            self.ctx
+                .sql(query)
+                .await
+                .map_err(|e| exon_planning_error(e, query))
+        })?;
+
+        match params {
+            Some(params) => Ok(bind_param_values(df, params)?),
+            None => Ok(df),
+        }
+    }
+
+    /// Get the schema provider for the given catalog and schema, falling back to the defaults.
+    fn schema_provider(
+        &self,
+        catalog: Option<&str>,
+        schema: Option<&str>,
+    ) -> error::BioBearResult<Arc<dyn SchemaProvider>> {
+        let state = self.ctx.session.state();
+        let defaults = &state.config().options().catalog;
+
+        let catalog_name = catalog.unwrap_or(&defaults.default_catalog);
+        let schema_name = schema.unwrap_or(&defaults.default_schema);
+
+        let catalog = self.ctx.session.catalog(catalog_name).ok_or_else(|| {
+            error::BioBearError::new(&format!("Catalog not found: {catalog_name}"))
+        })?;
+
+        catalog.schema(schema_name).ok_or_else(|| {
+            error::BioBearError::new(&format!("Schema not found: {catalog_name}.{schema_name}"))
+        })
+    }
+
+    /// Get a handle to this session that can be moved into a spawned future.
+    ///
+    /// The handle shares the catalog, config and runtime with this session.
+    fn detached_session(&self) -> ExonSession {
+        ExonSession {
+            session: self.ctx.session.clone(),
+        }
+    }
+
+    /// Register the object store on this session's runtime, replacing any store for the same URL.
+    fn register_object_store(&self, registration: ObjectStoreRegistration) {
+        self.ctx
+            .session
+            .runtime_env()
+            .register_object_store(registration.url.as_ref(), registration.store);
+    }
+}
+
 #[pymethods]
 impl BioBearSessionContext {
     #[new]
-    fn try_new() -> PyResult<Self> {
-        let ctx = ExonSession::new_exon().unwrap();
+    #[pyo3(signature = (config=None))]
+    fn try_new(config: Option<BioBearSessionConfig>) -> PyResult<Self> {
+        let config = config.unwrap_or_default();
+
+        let ctx = ExonSession::with_config_rt_exon(config.session_config(), config.runtime_env()?)
+            .map_err(error::BioBearError::from)?;
 
         Ok(Self { ctx })
     }
 
     /// Read one or more VCF files from the given path.
-    #[pyo3(signature = (file_path, options=None))]
+    #[pyo3(signature = (file_path, options=None, *, progress=None))]
     fn read_vcf_file(
         &mut self,
         file_path: &str,
         options: Option<crate::datasources::vcf::VCFReadOptions>,
+        progress: Option<PyObject>,
         py: Python,
     ) -> PyResult<ExecutionResult> {
         let file_options = FileOptions::from(file_path);
@@ -57,17 +199,18 @@ impl BioBearSessionContext {
         file_options.set_from_file_options(&mut options)?;
 
         let result = self.ctx.read_vcf(file_path, options.into());
-        let df = wait_for_future(py, result).map_err(error::BioBearError::from)?;
+        let df = wait_for_future(py, result)?;
 
-        Ok(ExecutionResult::new(df))
+        Ok(ExecutionResult::with_progress_callback(df, progress))
     }
 
     /// Read a HMM Dom Tab file from the given path.
-    #[pyo3(signature = (file_path, options=None))]
+    #[pyo3(signature = (file_path, options=None, *, progress=None))]
     fn read_hmm_dom_tab_file(
         &mut self,
         file_path: &str,
         options: Option<HMMDomTabReadOptions>,
+        progress: Option<PyObject>,
         py: Python,
     ) -> PyResult<ExecutionResult> {
         let file_options = FileOptions::from(file_path);
@@ -76,17 +219,18 @@ impl BioBearSessionContext {
         file_options.set_from_file_options(&mut options)?;
 
         let result = self.ctx.read_hmm_dom_tab(file_path, options.into());
-        let df = wait_for_future(py, result).map_err(error::BioBearError::from)?;
+        let df = wait_for_future(py, result)?;
 
-        Ok(ExecutionResult::new(df))
+        Ok(ExecutionResult::with_progress_callback(df, progress))
     }
 
     /// Read an SDF file from the given path.
-    #[pyo3(signature = (file_path, options=None))]
+    #[pyo3(signature = (file_path, options=None, *, progress=None))]
     fn read_sdf_file(
         &mut self,
         file_path: &str,
         options: Option<crate::datasources::sdf::SDFReadOptions>,
+        progress: Option<PyObject>,
         py: Python,
     ) -> PyResult<ExecutionResult> {
         let file_options = FileOptions::from(file_path);
@@ -95,17 +239,18 @@ impl BioBearSessionContext {
         file_options.set_from_file_options(&mut options)?;
 
         let result = self.ctx.read_sdf(file_path, options.into());
-        let df = wait_for_future(py, result).map_err(error::BioBearError::from)?;
+        let df = wait_for_future(py, result)?;
 
-        Ok(ExecutionResult::new(df))
+        Ok(ExecutionResult::with_progress_callback(df, progress))
     }
 
     /// Read a bigwig file from the given path.
-    #[pyo3(signature = (file_path, options=None))]
+    #[pyo3(signature = (file_path, options=None, *, progress=None))]
     fn read_bigwig_file(
         &mut self,
         file_path: &str,
         options: Option<BigWigReadOptions>,
+        progress: Option<PyObject>,
         py: Python,
     ) -> PyResult<ExecutionResult> {
         let options = options.unwrap_or_default();
@@ -114,27 +259,28 @@ impl BioBearSessionContext {
             Some(_) => {
                 let options = bigwig::zoom::ListingTableOptions::try_from(options)?;
                 let result = self.ctx.read_bigwig_zoom(file_path, options);
-                let df = wait_for_future(py, result).map_err(error::BioBearError::from)?;
+                let df = wait_for_future(py, result)?;
 
-                Ok(ExecutionResult::new(df))
+                Ok(ExecutionResult::with_progress_callback(df, progress))
             }
             None => {
                 let options = bigwig::value::ListingTableOptions::try_from(options)?;
 
                 let result = self.ctx.read_bigwig_view(file_path, options);
-                let df = wait_for_future(py, result).map_err(error::BioBearError::from)?;
+                let df = wait_for_future(py, result)?;
 
-                Ok(ExecutionResult::new(df))
+                Ok(ExecutionResult::with_progress_callback(df, progress))
             }
         }
     }
 
     /// Read a gff file from the given path.
-    #[pyo3(signature = (file_path, options=None))]
+    #[pyo3(signature = (file_path, options=None, *, progress=None))]
     fn read_gff_file(
         &mut self,
         file_path: &str,
         options: Option<crate::datasources::gff::GFFReadOptions>,
+        progress: Option<PyObject>,
         py: Python,
     ) -> PyResult<ExecutionResult> {
         let file_options = FileOptions::from(file_path);
@@ -142,17 +288,18 @@ impl BioBearSessionContext {
         options.update_from_file_options(&file_options)?;
 
         let result = self.ctx.read_gff(file_path, options.into());
-        let df = wait_for_future(py, result).map_err(error::BioBearError::from)?;
+        let df = wait_for_future(py, result)?;
 
-        Ok(ExecutionResult::new(df))
+        Ok(ExecutionResult::with_progress_callback(df, progress))
     }
 
     /// Read a fastq file from the given path.
-    #[pyo3(signature = (file_path, options=None))]
+    #[pyo3(signature = (file_path, options=None, *, progress=None))]
     fn read_fastq_file(
         &mut self,
         file_path: &str,
         options: Option<FASTQReadOptions>,
+        progress: Option<PyObject>,
         py: Python,
     ) -> PyResult<ExecutionResult> {
         let file_options = FileOptions::from(file_path);
@@ -161,17 +308,18 @@ impl BioBearSessionContext {
         file_options.set_from_file_options(&mut options)?;
 
         let result = self.ctx.read_fastq(file_path, options.into());
-        let df = wait_for_future(py, result).map_err(error::BioBearError::from)?;
+        let df = wait_for_future(py, result)?;
 
-        Ok(ExecutionResult::new(df))
+        Ok(ExecutionResult::with_progress_callback(df, progress))
     }
 
     /// Read a genbank file from the given path.
-    #[pyo3(signature = (file_path, options=None))]
+    #[pyo3(signature = (file_path, options=None, *, progress=None))]
     fn read_genbank_file(
         &mut self,
         file_path: &str,
         options: Option<crate::datasources::genbank::GenBankReadOptions>,
+        progress: Option<PyObject>,
         py: Python,
     ) -> PyResult<ExecutionResult> {
         let file_options = FileOptions::from(file_path);
@@ -180,33 +328,35 @@ impl BioBearSessionContext {
         file_options.set_from_file_options(&mut options)?;
 
         let result = self.ctx.read_genbank(file_path, options.into());
-        let df = wait_for_future(py, result).map_err(error::BioBearError::from)?;
+        let df = wait_for_future(py, result)?;
 
-        Ok(ExecutionResult::new(df))
+        Ok(ExecutionResult::with_progress_callback(df, progress))
     }
 
     /// Read a CRAM file from the given path.
-    #[pyo3(signature = (file_path, options=None))]
+    #[pyo3(signature = (file_path, options=None, *, progress=None))]
     fn read_cram_file(
         &mut self,
         file_path: &str,
         options: Option<crate::datasources::cram::CRAMReadOptions>,
+        progress: Option<PyObject>,
         py: Python,
     ) -> PyResult<ExecutionResult> {
         let options = options.unwrap_or_default();
 
         let result = self.ctx.read_cram(file_path, options.into());
-        let df = wait_for_future(py, result).map_err(error::BioBearError::from)?;
+        let df = wait_for_future(py, result)?;
 
-        Ok(ExecutionResult::new(df))
+        Ok(ExecutionResult::with_progress_callback(df, progress))
     }
 
     /// Read a mzml file from the given path.
-    #[pyo3(signature = (file_path, options=None))]
+    #[pyo3(signature = (file_path, options=None, *, progress=None))]
     fn read_mzml_file(
         &mut self,
         file_path: &str,
         options: Option<MzMLReadOptions>,
+        progress: Option<PyObject>,
         py: Python,
     ) -> PyResult<ExecutionResult> {
         let file_options = FileOptions::from(file_path);
@@ -215,17 +365,18 @@ impl BioBearSessionContext {
         file_options.set_from_file_options(&mut options)?;
 
         let result = self.ctx.read_mzml(file_path, options.into());
-        let df = wait_for_future(py, result).map_err(error::BioBearError::from)?;
+        let df = wait_for_future(py, result)?;
 
-        Ok(ExecutionResult::new(df))
+        Ok(ExecutionResult::with_progress_callback(df, progress))
     }
 
     /// Read a GTF file from the given path.
-    #[pyo3(signature = (file_path, options=None))]
+    #[pyo3(signature = (file_path, options=None, *, progress=None))]
     fn read_gtf_file(
         &mut self,
         file_path: &str,
         options: Option<crate::datasources::gtf::GTFReadOptions>,
+        progress: Option<PyObject>,
         py: Python,
     ) -> PyResult<ExecutionResult> {
         let file_options = FileOptions::from(file_path);
@@ -234,33 +385,35 @@ impl BioBearSessionContext {
         file_options.set_from_file_options(&mut options)?;
 
         let result = self.ctx.read_gtf(file_path, options.into());
-        let df = wait_for_future(py, result).map_err(error::BioBearError::from)?;
+        let df = wait_for_future(py, result)?;
 
-        Ok(ExecutionResult::new(df))
+        Ok(ExecutionResult::with_progress_callback(df, progress))
     }
 
     /// Read a BCF file from the given path.
-    #[pyo3(signature = (file_path, options=None))]
+    #[pyo3(signature = (file_path, options=None, *, progress=None))]
     fn read_bcf_file(
         &mut self,
         file_path: &str,
         options: Option<BCFReadOptions>,
+        progress: Option<PyObject>,
         py: Python,
     ) -> PyResult<ExecutionResult> {
         let options = options.unwrap_or_default();
 
         let result = self.ctx.read_bcf(file_path, options.into());
-        let df = wait_for_future(py, result).map_err(error::BioBearError::from)?;
+        let df = wait_for_future(py, result)?;
 
-        Ok(ExecutionResult::new(df))
+        Ok(ExecutionResult::with_progress_callback(df, progress))
     }
 
     /// Read a fasta file from the given path.
-    #[pyo3(signature = (file_path, options=None))]
+    #[pyo3(signature = (file_path, options=None, *, progress=None))]
     fn read_fasta_file(
         &mut self,
         file_path: &str,
         options: Option<FASTAReadOptions>,
+        progress: Option<PyObject>,
         py: Python,
     ) -> PyResult<ExecutionResult> {
         let file_options = FileOptions::from(file_path);
@@ -269,17 +422,18 @@ impl BioBearSessionContext {
         file_options.set_from_file_options(&mut options)?;
 
         let result = self.ctx.read_fasta(file_path, options.into());
-        let df = wait_for_future(py, result).map_err(error::BioBearError::from)?;
+        let df = wait_for_future(py, result)?;
 
-        Ok(ExecutionResult::new(df))
+        Ok(ExecutionResult::with_progress_callback(df, progress))
     }
 
     /// Read a BED file from the given path.
-    #[pyo3(signature = (file_path, options=None))]
+    #[pyo3(signature = (file_path, options=None, *, progress=None))]
     fn read_bed_file(
         &mut self,
         file_path: &str,
         options: Option<crate::datasources::bed::BEDReadOptions>,
+        progress: Option<PyObject>,
         py: Python,
     ) -> PyResult<ExecutionResult> {
         let file_options = FileOptions::from(file_path);
@@ -288,57 +442,398 @@ impl BioBearSessionContext {
         file_options.set_from_file_options(&mut options)?;
 
         let result = self.ctx.read_bed(file_path, options.into());
-        let df = wait_for_future(py, result).map_err(error::BioBearError::from)?;
+        let df = wait_for_future(py, result)?;
 
-        Ok(ExecutionResult::new(df))
+        Ok(ExecutionResult::with_progress_callback(df, progress))
     }
 
     /// Read a BAM file from the given path.
-    #[pyo3(signature = (file_path, options=None))]
+    #[pyo3(signature = (file_path, options=None, *, progress=None))]
     fn read_bam_file(
         &mut self,
         file_path: &str,
         options: Option<crate::datasources::bam::BAMReadOptions>,
+        progress: Option<PyObject>,
         py: Python,
     ) -> PyResult<ExecutionResult> {
         let options = options.unwrap_or_default();
 
         let result = self.ctx.read_bam(file_path, options.into());
-        let df = wait_for_future(py, result).map_err(error::BioBearError::from)?;
+        let df = wait_for_future(py, result)?;
 
-        Ok(ExecutionResult::new(df))
+        Ok(ExecutionResult::with_progress_callback(df, progress))
     }
 
     /// Read a SAM file from the given path.
-    #[pyo3(signature = (file_path, options=None))]
+    #[pyo3(signature = (file_path, options=None, *, progress=None))]
     fn read_sam_file(
         &mut self,
         file_path: &str,
         options: Option<crate::datasources::sam::SAMReadOptions>,
+        progress: Option<PyObject>,
         py: Python,
     ) -> PyResult<ExecutionResult> {
         let options = options.unwrap_or_default();
 
         let result = self.ctx.read_sam(file_path, options.into());
-        let df = wait_for_future(py, result).map_err(error::BioBearError::from)?;
+        let df = wait_for_future(py, result)?;
 
-        Ok(ExecutionResult::new(df))
+        Ok(ExecutionResult::with_progress_callback(df, progress))
     }
 
     /// Generate the plan from a SQL query and return the result as a [`PyExecutionResult`].
-    fn sql(&mut self, query: &str, py: Python) -> PyResult<ExecutionResult> {
-        let result = self.ctx.sql(query);
-        let df = wait_for_future(py, result).map_err(error::BioBearError::from)?;
+    ///
+    /// Placeholders in the query are bound to `params`, either a list for positional
+    /// parameters (`$1`) or a dict for named parameters (`$region`).
+    #[pyo3(signature = (query, params=None))]
+    fn sql(
+        &mut self,
+        query: &str,
+        params: Option<&Bound<'_, PyAny>>,
+        py: Python,
+    ) -> PyResult<ExecutionResult> {
+        let df = self.sql_with_params(query, params, py)?;
+
+        Ok(ExecutionResult::new(df))
+    }
+
+    /// Plan the SQL query without blocking the event loop, returning an awaitable
+    /// [`ExecutionResult`].
+    #[pyo3(signature = (query, params=None))]
+    fn sql_async(
+        &self,
+        query: &str,
+        params: Option<&Bound<'_, PyAny>>,
+        py: Python,
+    ) -> PyResult<PyObject> {
+        let params = params.map(py_to_param_values).transpose()?;
+        let ctx = self.detached_session();
+        let query = query.to_string();
+
+        future_into_py(
+            py,
+            async move { Ok(plan_detached(ctx, query, params).await?) },
+            |py, df| Ok(ExecutionResult::new(df).into_py(py)),
+        )
+    }
+
+    /// Plan the SQL query without executing it, returning the pyarrow Schema of its result.
+    ///
+    /// Raises `SQLSyntaxError` if the query can't be parsed, or `PlanningError` if it can't be
+    /// planned, e.g. it references an unknown table or column. Both have `line` and `column`
+    /// attributes when the parser reports a position.
+    #[pyo3(signature = (query, params=None))]
+    fn plan(
+        &self,
+        query: &str,
+        params: Option<&Bound<'_, PyAny>>,
+        py: Python,
+    ) -> PyResult<PyArrowType<Schema>> {
+        Ok(PyArrowType(self.plan_schema(query, params, py)?))
+    }
+
+    /// Check the SQL query can be planned without executing it, returning the `PlanningError`
+    /// it would raise, or None if it's valid.
+    #[pyo3(signature = (query, params=None))]
+    fn validate(
+        &self,
+        query: &str,
+        params: Option<&Bound<'_, PyAny>>,
+        py: Python,
+    ) -> PyResult<Option<PyObject>> {
+        match self.plan_schema(query, params, py) {
+            Ok(_) => Ok(None),
+            Err(err) if err.is_instance_of::<error::PlanningError>(py) => {
+                Ok(Some(err.into_value(py).into_any()))
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Run the statements of a SQL script in order, returning the result of the last one.
+    ///
+    /// `script` is either the SQL text as a `str`, or the path of a file containing it as an
+    /// `os.PathLike`, e.g. a `pathlib.Path`. Every statement but the last is executed, and the
+    /// last is returned as an [`ExecutionResult`] like `sql`. If a statement fails, its error is
+    /// raised with the statement's position in the script, and `statement_index` and `statement`
+    /// attributes.
+    ///
+    /// The script is split into statements with the dialect `ExonSession::sql` parses each of
+    /// them with, so the two agree on where statements end.
+    fn execute_script(
+        &mut self,
+        script: &Bound<'_, PyAny>,
+        py: Python,
+    ) -> PyResult<ExecutionResult> {
+        let script = read_script(script)?;
+
+        let statements = parse_script(&script, EXON_DIALECT).map_err(error::BioBearError::from)?;
+
+        let Some((last, statements)) = statements.split_last() else {
+            return Err(error::BioBearError::InvalidConfiguration(
+                "The script contains no statements".to_string(),
+            )
+            .into());
+        };
+
+        let plan_statement = |statement: &ScriptStatement| {
+            wait_for_future(py, async {
+                self.ctx
+                    .sql(&statement.text)
+                    .await
+                    .map_err(|e| exon_planning_error(e, &statement.text))
+            })
+        };
+
+        let count = statements.len() + 1;
+        for (index, statement) in statements.iter().enumerate() {
+            let df = plan_statement(statement)
+                .map_err(|err| statement_error(py, err, index, count, statement))?;
+
+            wait_for_cancellable_future(py, df.collect(), &CancellationToken::new())
+                .map_err(|err| statement_error(py, err, index, count, statement))?;
+        }
+
+        let df =
+            plan_statement(last).map_err(|err| statement_error(py, err, count - 1, count, last))?;
 
         Ok(ExecutionResult::new(df))
     }
 
+    /// Plan a SQL query once so it can be executed repeatedly with different parameters.
+    fn prepare(&mut self, query: &str, py: Python) -> PyResult<PreparedQuery> {
+        let df = wait_for_future(py, async {
+            self.ctx
+                .sql(query)
+                .await
+                .map_err(|e| exon_planning_error(e, query))
+        })?;
+
+        Ok(PreparedQuery::new(df))
+    }
+
     /// Execute the SQL query eagerly, but do not collect the results.
-    fn execute(&mut self, query: &str, py: Python) -> PyResult<()> {
-        let result = self.ctx.sql(query);
-        let df = wait_for_future(py, result).map_err(error::BioBearError::from)?;
+    #[pyo3(signature = (query, params=None))]
+    fn execute(
+        &mut self,
+        query: &str,
+        params: Option<&Bound<'_, PyAny>>,
+        py: Python,
+    ) -> PyResult<()> {
+        let df = self.sql_with_params(query, params, py)?;
+
+        wait_for_cancellable_future(py, df.collect(), &CancellationToken::new())?;
+
+        Ok(())
+    }
+
+    /// Execute the SQL query without blocking the event loop, but do not collect the results.
+    #[pyo3(signature = (query, params=None))]
+    fn execute_async(
+        &self,
+        query: &str,
+        params: Option<&Bound<'_, PyAny>>,
+        py: Python,
+    ) -> PyResult<PyObject> {
+        let params = params.map(py_to_param_values).transpose()?;
+        let ctx = self.detached_session();
+        let query = query.to_string();
+
+        future_into_py(
+            py,
+            async move {
+                let df = plan_detached(ctx, query, params).await?;
+                df.collect().await.map_err(error::BioBearError::from)?;
+
+                Ok(())
+            },
+            |py, ()| Ok(py.None()),
+        )
+    }
+
+    /// Register an in-memory Arrow, Polars or pandas object as a table with the given name.
+    ///
+    /// Record batch readers are registered as streaming tables and can only be scanned once.
+    fn register_table(&mut self, name: &str, obj: &Bound<'_, PyAny>) -> PyResult<()> {
+        let table = table_provider_from_pyobject(obj)?;
+
+        self.ctx
+            .session
+            .register_table(name, table)
+            .map_err(error::BioBearError::from)?;
 
-        wait_for_future(py, df.collect()).map_err(error::BioBearError::from)?;
+        Ok(())
+    }
+
+    /// Set a DataFusion or Exon configuration option for subsequent queries, e.g.
+    /// `datafusion.execution.batch_size` or `exon.vcf_parse_info`.
+    ///
+    /// Raises `ValueError` if the option doesn't exist or the value isn't valid for it.
+    fn set(&self, key: &str, value: &Bound<'_, PyAny>) -> PyResult<()> {
+        let value = if value.is_instance_of::<PyBool>() {
+            value.extract::<bool>()?.to_string()
+        } else {
+            value.str()?.to_str()?.to_string()
+        };
+
+        let state = self.ctx.session.state_ref();
+        let mut state = state.write();
+        set_config_option(state.config_mut(), key, &value)?;
+
+        Ok(())
+    }
+
+    /// Get the value of a configuration option as a string, or None if it's unset.
+    ///
+    /// Raises `ValueError` if the option doesn't exist.
+    fn get(&self, key: &str) -> PyResult<Option<String>> {
+        let state = self.ctx.session.state();
+
+        let (_, value) = config_entries(state.config())
+            .into_iter()
+            .find(|(k, _)| k == key)
+            .ok_or_else(|| {
+                error::BioBearError::InvalidConfiguration(format!(
+                    "Unknown configuration option: {key}"
+                ))
+            })?;
+
+        Ok(value)
+    }
+
+    /// Get all the configuration options, mapping each key to its value, or None if it's unset.
+    fn settings(&self) -> HashMap<String, Option<String>> {
+        let state = self.ctx.session.state();
+
+        config_entries(state.config()).into_iter().collect()
+    }
+
+    /// List the catalogs registered with the session.
+    fn catalogs(&self) -> Vec<String> {
+        let mut names = self.ctx.session.catalog_names();
+        names.sort();
+
+        names
+    }
+
+    /// List the schemas in the given catalog, or the default catalog if not given.
+    #[pyo3(signature = (catalog=None))]
+    fn schemas(&self, catalog: Option<&str>) -> PyResult<Vec<String>> {
+        let state = self.ctx.session.state();
+        let catalog_name = catalog.unwrap_or(&state.config().options().catalog.default_catalog);
+
+        let catalog = self.ctx.session.catalog(catalog_name).ok_or_else(|| {
+            error::BioBearError::new(&format!("Catalog not found: {catalog_name}"))
+        })?;
+
+        let mut names = catalog.schema_names();
+        names.sort();
+
+        Ok(names)
+    }
+
+    /// List the tables in the given schema, or the default schema if not given.
+    #[pyo3(signature = (catalog=None, schema=None))]
+    fn tables(&self, catalog: Option<&str>, schema: Option<&str>) -> PyResult<Vec<String>> {
+        let schema = self.schema_provider(catalog, schema)?;
+
+        let mut names = schema.table_names();
+        names.sort();
+
+        Ok(names)
+    }
+
+    /// Check if a table with the given name exists.
+    fn table_exists(&self, name: &str) -> PyResult<bool> {
+        let exists = self
+            .ctx
+            .session
+            .table_exist(name)
+            .map_err(error::BioBearError::from)?;
+
+        Ok(exists)
+    }
+
+    /// Return the Arrow schema of the table with the given name.
+    fn table_schema(&self, name: &str, py: Python) -> PyResult<PyArrowType<Schema>> {
+        let result = self.ctx.session.table_provider(name);
+        let table = wait_for_future(py, result)?;
+
+        Ok(PyArrowType(table.schema().as_ref().clone()))
+    }
+
+    /// Deregister the table with the given name, returning True if a table was removed.
+    fn deregister_table(&mut self, name: &str) -> PyResult<bool> {
+        let table = self
+            .ctx
+            .session
+            .deregister_table(name)
+            .map_err(error::BioBearError::from)?;
+
+        Ok(table.is_some())
+    }
+
+    /// Register a Python function as a scalar UDF callable from SQL.
+    ///
+    /// The function is called once per batch with a pyarrow Array for each argument and must
+    /// return a pyarrow Array of the same length.
+    #[pyo3(signature = (name, func, input_types, return_type, volatility="volatile"))]
+    fn register_udf(
+        &mut self,
+        name: &str,
+        func: PyObject,
+        input_types: Vec<PyArrowType<DataType>>,
+        return_type: PyArrowType<DataType>,
+        volatility: &str,
+    ) -> PyResult<()> {
+        let volatility = parse_volatility(volatility)?;
+        let input_types = input_types.into_iter().map(|t| t.0).collect();
+
+        let udf = create_python_udf(name, func, input_types, return_type.0, volatility);
+        self.ctx.session.register_udf(udf);
+
+        Ok(())
+    }
+
+    /// Register a Python accumulator class as an aggregate UDF callable from SQL.
+    ///
+    /// The class is instantiated once per group and partition, and must implement
+    /// `update(*values)`, `merge(*states)`, `state()` and `evaluate()`. Partial states produced
+    /// by `state()` are merged across partitions with `merge()`.
+    #[pyo3(signature = (name, accumulator, input_types, return_type, state_types, volatility="volatile"))]
+    fn register_udaf(
+        &mut self,
+        name: &str,
+        accumulator: PyObject,
+        input_types: Vec<PyArrowType<DataType>>,
+        return_type: PyArrowType<DataType>,
+        state_types: Vec<PyArrowType<DataType>>,
+        volatility: &str,
+    ) -> PyResult<()> {
+        let volatility = parse_volatility(volatility)?;
+        let input_types = input_types.into_iter().map(|t| t.0).collect();
+        let state_types = state_types.into_iter().map(|t| t.0).collect();
+
+        let udaf = create_python_udaf(
+            name,
+            accumulator,
+            input_types,
+            return_type.0,
+            state_types,
+            volatility,
+        );
+        self.ctx.session.register_udaf(udaf);
+
+        Ok(())
+    }
+
+    /// Register a Python function as a table function, e.g. `SELECT * FROM my_scan('path')`.
+    ///
+    /// The function is called with the literal arguments from the query and may return a
+    /// RecordBatchReader, an iterator of RecordBatches, or anything accepted by `register_table`.
+    fn register_udtf(&mut self, name: &str, func: PyObject) -> PyResult<()> {
+        let udtf = PythonTableFunction::new(name, func);
+        self.ctx.session.register_udtf(name, Arc::new(udtf));
 
         Ok(())
     }
@@ -347,18 +842,215 @@ impl BioBearSessionContext {
     fn register_object_store_from_url(&mut self, url: &str, py: Python) -> PyResult<()> {
         let runtime = self.ctx.session.runtime_env();
         let registration = runtime.exon_register_object_store_uri(url);
-        wait_for_future(py, registration).map_err(error::BioBearError::from)?;
+        wait_for_future(py, registration)?;
+
+        Ok(())
+    }
+
+    /// Register an S3 (or S3-compatible, e.g. MinIO) bucket with explicit credentials and endpoint.
+    ///
+    /// The bucket is then available to queries as `s3://<bucket>/...` in this session only.
+    #[pyo3(signature = (bucket, endpoint=None, region=None, access_key=None, secret_key=None, session_token=None, allow_http=false))]
+    #[allow(clippy::too_many_arguments)]
+    fn register_s3(
+        &mut self,
+        bucket: &str,
+        endpoint: Option<String>,
+        region: Option<String>,
+        access_key: Option<String>,
+        secret_key: Option<String>,
+        session_token: Option<String>,
+        allow_http: bool,
+    ) -> PyResult<()> {
+        let options = S3Options {
+            bucket: bucket.to_string(),
+            endpoint,
+            region,
+            access_key,
+            secret_key,
+            session_token,
+            allow_http,
+        };
+
+        self.register_object_store(options.build()?);
 
         Ok(())
     }
+
+    /// Register a Google Cloud Storage bucket with explicit service account credentials.
+    ///
+    /// The bucket is then available to queries as `gs://<bucket>/...` in this session only.
+    #[pyo3(signature = (bucket, service_account_path=None, service_account_key=None))]
+    fn register_gcs(
+        &mut self,
+        bucket: &str,
+        service_account_path: Option<String>,
+        service_account_key: Option<String>,
+    ) -> PyResult<()> {
+        let options = GcsOptions {
+            bucket: bucket.to_string(),
+            service_account_path,
+            service_account_key,
+        };
+
+        self.register_object_store(options.build()?);
+
+        Ok(())
+    }
+
+    /// Register an Azure Blob Storage container with explicit credentials and endpoint.
+    ///
+    /// The container is then available to queries as `az://<container>/...` in this session only.
+    #[pyo3(signature = (account, container, access_key=None, endpoint=None, allow_http=false))]
+    fn register_azure(
+        &mut self,
+        account: &str,
+        container: &str,
+        access_key: Option<String>,
+        endpoint: Option<String>,
+        allow_http: bool,
+    ) -> PyResult<()> {
+        let options = AzureOptions {
+            account: account.to_string(),
+            container: container.to_string(),
+            access_key,
+            endpoint,
+            allow_http,
+        };
+
+        self.register_object_store(options.build()?);
+
+        Ok(())
+    }
+}
+
+/// Plan the SQL query on a detached session and bind any parameters to its placeholders.
+async fn plan_detached(
+    ctx: ExonSession,
+    query: String,
+    params: Option<ParamValues>,
+) -> error::BioBearResult<DataFrame> {
+    let df = ctx
+        .sql(&query)
+        .await
+        .map_err(|e| exon_planning_error(e, &query))?;
+
+    match params {
+        Some(params) => bind_param_values(df, params),
+        None => Ok(df),
+    }
+}
+
+/// Read a script given as SQL text, or as an `os.PathLike` path of a file containing it.
+///
+/// A `str` is always SQL text, so what's run doesn't depend on whether a file of that name exists.
+fn read_script(script: &Bound<'_, PyAny>) -> PyResult<String> {
+    if let Ok(text) = script.downcast::<PyString>() {
+        return Ok(text.to_str()?.to_string());
+    }
+
+    let path: PathBuf = script.extract()?;
+    Ok(std::fs::read_to_string(path)?)
+}
+
+/// Add the failed statement's position in the script to its error, adjusting the error's own
+/// position, if any, to be relative to the script.
+fn statement_error(
+    py: Python,
+    err: PyErr,
+    index: usize,
+    count: usize,
+    statement: &ScriptStatement,
+) -> PyErr {
+    // Leave e.g. KeyboardInterrupt as is.
+    if !err.is_instance_of::<PyException>(py) {
+        return err;
+    }
+
+    let value = err.value_bound(py);
+    let message = format!(
+        "Statement {} of {} (line {}) failed: {}",
+        index + 1,
+        count,
+        statement.line,
+        value
+    );
+
+    let result = (|| -> PyResult<()> {
+        value.setattr("args", (message,))?;
+        value.setattr("statement_index", index)?;
+        value.setattr("statement", &statement.text)?;
+
+        let line = value.getattr("line").and_then(|line| line.extract::<u64>());
+        if let Ok(line) = line {
+            let column: u64 = value.getattr("column")?.extract()?;
+
+            if line == 1 {
+                value.setattr("column", statement.column + column - 1)?;
+            }
+            value.setattr("line", statement.line + line - 1)?;
+        }
+
+        Ok(())
+    })();
+
+    match result {
+        Ok(()) => err,
+        Err(e) => e,
+    }
+}
+
+/// Convert an error from planning the query, adding the position of syntax errors that
+/// DataFusion's parser reports without one.
+fn planning_error(err: DataFusionError, query: &str, dialect: &str) -> error::BioBearError {
+    match error::BioBearError::planning(err) {
+        error::BioBearError::SQLSyntaxError(msg) => syntax_error(msg, query, dialect),
+        err => err,
+    }
+}
+
+/// Create a syntax error for the query, adding the position the parser reports if `msg` doesn't
+/// include one.
+fn syntax_error(msg: String, query: &str, dialect: &str) -> error::BioBearError {
+    if msg.contains(" at Line: ") {
+        return error::BioBearError::SQLSyntaxError(msg);
+    }
+
+    match parse_script(query, dialect) {
+        Err(err) => error::BioBearError::SQLSyntaxError(format!("{msg}{}", err.location)),
+        Ok(_) => error::BioBearError::SQLSyntaxError(msg),
+    }
+}
+
+/// Convert an error from planning a query with Exon, distinguishing syntax and planning errors
+/// as [`planning_error`] does.
+fn exon_planning_error(err: ExonError, query: &str) -> error::BioBearError {
+    match err {
+        ExonError::DataFusionError(e) => planning_error(e, query, EXON_DIALECT),
+        ExonError::ParserError(msg) => syntax_error(msg, query, EXON_DIALECT),
+        e => e.into(),
+    }
+}
+
+/// Whether `ExonSession::sql` plans the statement itself, as a COPY to an Exon file format.
+fn is_exon_copy(statement: &Statement) -> bool {
+    matches!(
+        statement,
+        Statement::CopyTo(copy) if copy
+            .stored_as
+            .as_deref()
+            .is_some_and(|format| ExonFileType::from_str(format).is_ok())
+    )
 }
 
 #[pyfunction]
-pub fn connect() -> PyResult<BioBearSessionContext> {
-    BioBearSessionContext::try_new()
+#[pyo3(signature = (config=None))]
+pub fn connect(config: Option<BioBearSessionConfig>) -> PyResult<BioBearSessionContext> {
+    BioBearSessionContext::try_new(config)
 }
 
 #[pyfunction]
-pub fn new_session() -> PyResult<BioBearSessionContext> {
-    BioBearSessionContext::try_new()
+#[pyo3(signature = (config=None))]
+pub fn new_session(config: Option<BioBearSessionConfig>) -> PyResult<BioBearSessionContext> {
+    BioBearSessionContext::try_new(config)
 }
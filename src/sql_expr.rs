@@ -0,0 +1,176 @@
+// Copyright 2024 WHERE TRUE Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use datafusion::{
+    common::DFSchema,
+    execution::SessionState,
+    logical_expr::{expr_fn::qualified_wildcard, Expr, SortExpr},
+    prelude::{wildcard, DataFrame},
+    sql::sqlparser::{
+        ast::{Ident, SelectItem, WildcardAdditionalOptions},
+        dialect::{dialect_from_str, Dialect},
+        parser::{Parser, ParserError},
+        tokenizer::Token,
+    },
+};
+
+use crate::error::{BioBearError, BioBearResult};
+
+/// Parses SQL expressions, e.g. `start + 1 AS next`, into expressions over a schema, using the
+/// session's functions and SQL dialect.
+pub(crate) struct SqlExprParser {
+    state: SessionState,
+    schema: DFSchema,
+}
+
+impl SqlExprParser {
+    /// Parse expressions over the DataFrame's columns.
+    pub(crate) fn new(df: &DataFrame) -> Self {
+        let schema = df.schema().clone();
+        let (state, _) = df.clone().into_parts();
+
+        Self { state, schema }
+    }
+
+    /// Parse expressions over the schema's columns instead, e.g. those of both sides of a join.
+    pub(crate) fn with_schema(self, schema: DFSchema) -> Self {
+        Self { schema, ..self }
+    }
+
+    /// Parse an expression as it would appear in a SELECT list, optionally with an alias.
+    pub(crate) fn parse_expr(&self, sql: &str) -> BioBearResult<Expr> {
+        let item = self.parse(sql, |parser| parser.parse_select_item())?;
+
+        match item {
+            SelectItem::UnnamedExpr(expr) => self.to_logical_expr(&expr),
+            SelectItem::ExprWithAlias { expr, alias } => {
+                Ok(self.to_logical_expr(&expr)?.alias(self.normalize(alias)))
+            }
+            SelectItem::Wildcard(options) if options == WildcardAdditionalOptions::default() => {
+                Ok(wildcard())
+            }
+            SelectItem::QualifiedWildcard(name, options)
+                if options == WildcardAdditionalOptions::default() =>
+            {
+                Ok(qualified_wildcard(name.to_string()))
+            }
+            _ => Err(BioBearError::PlanningError(format!(
+                "Unsupported wildcard options in expression: {sql}"
+            ))),
+        }
+    }
+
+    /// Parse an expression as it would appear in an ORDER BY clause, e.g. `start DESC`.
+    pub(crate) fn parse_sort_expr(&self, sql: &str) -> BioBearResult<SortExpr> {
+        let order_by = self.parse(sql, |parser| parser.parse_order_by_expr())?;
+
+        if order_by.with_fill.is_some() {
+            return Err(BioBearError::PlanningError(format!(
+                "Unsupported WITH FILL in sort expression: {sql}"
+            )));
+        }
+
+        // Nulls sort first in descending order unless specified, as they do in SQL.
+        let asc = order_by.asc.unwrap_or(true);
+        let nulls_first = order_by.nulls_first.unwrap_or(!asc);
+
+        Ok(self.to_logical_expr(&order_by.expr)?.sort(asc, nulls_first))
+    }
+
+    /// Parse the whole of the SQL text with `parse`, failing on any trailing input.
+    fn parse<T>(
+        &self,
+        sql: &str,
+        parse: impl FnOnce(&mut Parser) -> Result<T, ParserError>,
+    ) -> BioBearResult<T> {
+        let dialect = self.dialect()?;
+
+        let parsed = Parser::new(dialect.as_ref())
+            .try_with_sql(sql)
+            .and_then(|mut parser| {
+                let parsed = parse(&mut parser)?;
+                parser.expect_token(&Token::EOF)?;
+
+                Ok(parsed)
+            });
+
+        parsed.map_err(|e| BioBearError::SQLSyntaxError(e.to_string()))
+    }
+
+    /// Plan a parsed expression against the schema.
+    ///
+    /// The session only plans expressions from their SQL text, which the parsed expression
+    /// reproduces.
+    fn to_logical_expr(&self, expr: &datafusion::sql::sqlparser::ast::Expr) -> BioBearResult<Expr> {
+        self.state
+            .create_logical_expr(&expr.to_string(), &self.schema)
+            .map_err(BioBearError::planning)
+    }
+
+    fn dialect(&self) -> BioBearResult<Box<dyn Dialect>> {
+        let name = &self.state.config().options().sql_parser.dialect;
+
+        dialect_from_str(name).ok_or_else(|| {
+            BioBearError::InvalidConfiguration(format!("Unsupported SQL dialect: {name}"))
+        })
+    }
+
+    /// Normalize an alias the way SQL queries do, lowercasing it unless it's quoted.
+    fn normalize(&self, ident: Ident) -> String {
+        let normalize = self
+            .state
+            .config()
+            .options()
+            .sql_parser
+            .enable_ident_normalization;
+
+        if normalize && ident.quote_style.is_none() {
+            ident.value.to_lowercase()
+        } else {
+            ident.value
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::prelude::SessionContext;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_parse_exprs() -> BioBearResult<()> {
+        let ctx = SessionContext::new();
+        let df = ctx.sql("SELECT 1 AS x, 'a' AS y").await?;
+        let parser = SqlExprParser::new(&df);
+
+        let expr = parser.parse_expr("x + 1 AS Next")?;
+        assert_eq!(expr.schema_name().to_string(), "next");
+
+        let sort = parser.parse_sort_expr("y DESC")?;
+        assert!(!sort.asc);
+        assert!(sort.nulls_first);
+
+        assert!(matches!(
+            parser.parse_expr("x +"),
+            Err(BioBearError::SQLSyntaxError(_))
+        ));
+        assert!(matches!(
+            parser.parse_expr("z"),
+            Err(BioBearError::PlanningError(_))
+        ));
+
+        Ok(())
+    }
+}
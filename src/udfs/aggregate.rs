@@ -0,0 +1,152 @@
+// Copyright 2024 WHERE TRUE Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use arrow::{
+    array::{make_array, ArrayData, ArrayRef},
+    datatypes::DataType,
+    pyarrow::{FromPyArrow, ToPyArrow},
+};
+use datafusion::{
+    common::ScalarValue,
+    error::{DataFusionError, Result},
+    logical_expr::{create_udaf, Accumulator, AggregateUDF, Volatility},
+};
+use pyo3::{prelude::*, types::PyList};
+
+use super::scalar::arrays_to_pyarrow;
+
+/// Convert a pyarrow Scalar (or plain Python value) into a [`ScalarValue`] of the given type.
+fn scalar_from_python(
+    py: Python,
+    value: &Bound<'_, PyAny>,
+    data_type: &DataType,
+) -> PyResult<ScalarValue> {
+    let pyarrow = py.import_bound("pyarrow")?;
+
+    let values = PyList::new_bound(py, [value]);
+    let array = pyarrow.call_method1("array", (values, data_type.to_pyarrow(py)?))?;
+
+    let array = make_array(ArrayData::from_pyarrow_bound(&array)?);
+    let scalar = ScalarValue::try_from_array(&array, 0)
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+
+    Ok(scalar)
+}
+
+fn to_datafusion_error(e: PyErr) -> DataFusionError {
+    DataFusionError::External(Box::new(e))
+}
+
+/// An [`Accumulator`] backed by an instance of a Python accumulator class.
+///
+/// The Python object must implement `update(*values)`, `merge(*states)`, `state()` and
+/// `evaluate()`, where `update` and `merge` receive pyarrow Arrays.
+#[derive(Debug)]
+struct PythonAccumulator {
+    accumulator: PyObject,
+    return_type: DataType,
+    state_types: Arc<Vec<DataType>>,
+}
+
+impl Accumulator for PythonAccumulator {
+    fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
+        Python::with_gil(|py| {
+            let args = arrays_to_pyarrow(py, values)?;
+            self.accumulator.call_method1(py, "update", args)?;
+
+            Ok(())
+        })
+        .map_err(to_datafusion_error)
+    }
+
+    fn merge_batch(&mut self, states: &[ArrayRef]) -> Result<()> {
+        Python::with_gil(|py| {
+            let args = arrays_to_pyarrow(py, states)?;
+            self.accumulator.call_method1(py, "merge", args)?;
+
+            Ok(())
+        })
+        .map_err(to_datafusion_error)
+    }
+
+    fn state(&mut self) -> Result<Vec<ScalarValue>> {
+        let state = Python::with_gil(|py| {
+            let state = self.accumulator.call_method0(py, "state")?;
+            let state = state.bind(py).iter()?.collect::<PyResult<Vec<_>>>()?;
+
+            if state.len() != self.state_types.len() {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "Accumulator state returned {} values, expected {}",
+                    state.len(),
+                    self.state_types.len()
+                )));
+            }
+
+            state
+                .iter()
+                .zip(self.state_types.iter())
+                .map(|(value, data_type)| scalar_from_python(py, value, data_type))
+                .collect::<PyResult<Vec<_>>>()
+        })
+        .map_err(to_datafusion_error)?;
+
+        Ok(state)
+    }
+
+    fn evaluate(&mut self) -> Result<ScalarValue> {
+        Python::with_gil(|py| {
+            let value = self.accumulator.call_method0(py, "evaluate")?;
+            scalar_from_python(py, value.bind(py), &self.return_type)
+        })
+        .map_err(to_datafusion_error)
+    }
+
+    fn size(&self) -> usize {
+        std::mem::size_of_val(self)
+    }
+}
+
+/// Create an [`AggregateUDF`] that instantiates the Python accumulator class per group.
+pub(crate) fn create_python_udaf(
+    name: &str,
+    accumulator: PyObject,
+    input_types: Vec<DataType>,
+    return_type: DataType,
+    state_types: Vec<DataType>,
+    volatility: Volatility,
+) -> AggregateUDF {
+    let state_types = Arc::new(state_types);
+    let accumulator_return_type = return_type.clone();
+    let accumulator_state_types = state_types.clone();
+
+    create_udaf(
+        name,
+        input_types,
+        Arc::new(return_type),
+        volatility,
+        Arc::new(move |_| {
+            let instance =
+                Python::with_gil(|py| accumulator.call0(py)).map_err(to_datafusion_error)?;
+
+            Ok(Box::new(PythonAccumulator {
+                accumulator: instance,
+                return_type: accumulator_return_type.clone(),
+                state_types: accumulator_state_types.clone(),
+            }))
+        }),
+        state_types,
+    )
+}
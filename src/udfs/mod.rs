@@ -0,0 +1,51 @@
+// Copyright 2024 WHERE TRUE Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use datafusion::logical_expr::Volatility;
+
+use crate::error::{BioBearError, BioBearResult};
+
+pub mod aggregate;
+pub mod scalar;
+pub mod table;
+
+pub(crate) fn parse_volatility(volatility: &str) -> BioBearResult<Volatility> {
+    match volatility.to_lowercase().as_str() {
+        "immutable" => Ok(Volatility::Immutable),
+        "stable" => Ok(Volatility::Stable),
+        "volatile" => Ok(Volatility::Volatile),
+        _ => Err(BioBearError::InvalidConfiguration(format!(
+            "Invalid volatility: {volatility}, expected one of immutable, stable or volatile"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_volatility() {
+        assert_eq!(
+            parse_volatility("immutable").unwrap(),
+            Volatility::Immutable
+        );
+        assert_eq!(parse_volatility("Stable").unwrap(), Volatility::Stable);
+        assert_eq!(parse_volatility("VOLATILE").unwrap(), Volatility::Volatile);
+        assert!(matches!(
+            parse_volatility("sometimes"),
+            Err(BioBearError::InvalidConfiguration(_))
+        ));
+    }
+}
@@ -0,0 +1,101 @@
+// Copyright 2024 WHERE TRUE Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use arrow::{
+    array::{make_array, ArrayData, ArrayRef},
+    datatypes::DataType,
+    pyarrow::{FromPyArrow, ToPyArrow},
+};
+use datafusion::{
+    error::{DataFusionError, Result},
+    logical_expr::{create_udf, ColumnarValue, ScalarUDF, Volatility},
+};
+use pyo3::{prelude::*, types::PyTuple};
+
+/// Convert the arrays to a tuple of pyarrow Arrays, suitable for passing as `*args`.
+pub(crate) fn arrays_to_pyarrow<'py>(
+    py: Python<'py>,
+    arrays: &[ArrayRef],
+) -> PyResult<Bound<'py, PyTuple>> {
+    let arrays = arrays
+        .iter()
+        .map(|array| array.to_data().to_pyarrow(py))
+        .collect::<PyResult<Vec<_>>>()?;
+
+    Ok(PyTuple::new_bound(py, arrays))
+}
+
+/// Call a Python function with the arrays converted to pyarrow and convert the result back.
+fn call_python_with_arrays(py: Python, func: &PyObject, arrays: &[ArrayRef]) -> PyResult<ArrayRef> {
+    let args = arrays_to_pyarrow(py, arrays)?;
+
+    let result = func.bind(py).call1(args)?;
+    let data = ArrayData::from_pyarrow_bound(&result)?;
+
+    Ok(make_array(data))
+}
+
+/// Evaluate a Python scalar function over a batch of arguments.
+fn evaluate(
+    func: &PyObject,
+    return_type: &DataType,
+    args: &[ColumnarValue],
+) -> Result<ColumnarValue> {
+    let arrays = ColumnarValue::values_to_arrays(args)?;
+    let num_rows = arrays.first().map(|a| a.len());
+
+    let result = Python::with_gil(|py| call_python_with_arrays(py, func, &arrays))
+        .map_err(|e| DataFusionError::External(Box::new(e)))?;
+
+    if result.data_type() != return_type {
+        return Err(DataFusionError::Execution(format!(
+            "Python UDF returned {}, expected {}",
+            result.data_type(),
+            return_type
+        )));
+    }
+
+    if let Some(num_rows) = num_rows {
+        if result.len() != num_rows {
+            return Err(DataFusionError::Execution(format!(
+                "Python UDF returned {} rows, expected {}",
+                result.len(),
+                num_rows
+            )));
+        }
+    }
+
+    Ok(ColumnarValue::Array(result))
+}
+
+/// Create a [`ScalarUDF`] that calls the Python function one batch at a time.
+pub(crate) fn create_python_udf(
+    name: &str,
+    func: PyObject,
+    input_types: Vec<DataType>,
+    return_type: DataType,
+    volatility: Volatility,
+) -> ScalarUDF {
+    let expected_type = return_type.clone();
+
+    create_udf(
+        name,
+        input_types,
+        return_type,
+        volatility,
+        Arc::new(move |args: &[ColumnarValue]| evaluate(&func, &expected_type, args)),
+    )
+}
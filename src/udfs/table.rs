@@ -0,0 +1,90 @@
+// Copyright 2024 WHERE TRUE Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use arrow::pyarrow::ToPyArrow;
+use datafusion::{
+    catalog::TableProvider,
+    common::ScalarValue,
+    datasource::function::TableFunctionImpl,
+    error::{DataFusionError, Result},
+    logical_expr::Expr,
+};
+use pyo3::{prelude::*, types::PyTuple};
+
+use crate::{error::BioBearError, py_table::table_provider_from_pyobject};
+
+/// Convert a literal into the equivalent Python value, e.g. a Utf8 literal into a `str`.
+fn scalar_to_python(py: Python, scalar: &ScalarValue) -> PyResult<PyObject> {
+    let array = scalar.to_array().map_err(BioBearError::from)?;
+    let array = array.to_data().to_pyarrow(py)?;
+
+    array
+        .call_method1(py, "__getitem__", (0,))?
+        .call_method0(py, "as_py")
+}
+
+/// A table function that calls a Python function with the literal arguments from the query.
+///
+/// The Python function may return anything accepted by `register_table`, e.g. a
+/// `pyarrow.RecordBatchReader`, a generator of RecordBatches or a DataFrame.
+pub(crate) struct PythonTableFunction {
+    name: String,
+    func: PyObject,
+}
+
+impl PythonTableFunction {
+    pub(crate) fn new(name: &str, func: PyObject) -> Self {
+        Self {
+            name: name.to_string(),
+            func,
+        }
+    }
+}
+
+impl std::fmt::Debug for PythonTableFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PythonTableFunction")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+impl TableFunctionImpl for PythonTableFunction {
+    fn call(&self, exprs: &[Expr]) -> Result<Arc<dyn TableProvider>> {
+        let scalars = exprs
+            .iter()
+            .map(|expr| match expr {
+                Expr::Literal(scalar) => Ok(scalar),
+                _ => Err(DataFusionError::Plan(format!(
+                    "{} only accepts literal arguments, got {}",
+                    self.name, expr
+                ))),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Python::with_gil(|py| {
+            let args = scalars
+                .into_iter()
+                .map(|scalar| scalar_to_python(py, scalar))
+                .collect::<PyResult<Vec<_>>>()?;
+
+            let result = self.func.bind(py).call1(PyTuple::new_bound(py, args))?;
+
+            table_provider_from_pyobject(&result)
+        })
+        .map_err(|e| DataFusionError::External(Box::new(e)))
+    }
+}
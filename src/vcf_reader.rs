@@ -19,21 +19,19 @@ use exon::datasources::vcf::ListingVCFTableOptions;
 use exon::ffi::DataFrameRecordBatchStream;
 use noodles::core::Region;
 use pyo3::prelude::*;
-use tokio::runtime::Runtime;
 
 use exon::{new_exon_config, ExonSession};
 
 use std::io;
 use std::str::FromStr;
-use std::sync::Arc;
 
 use crate::error::BioBearError;
+use crate::runtime::get_tokio_runtime;
 
 #[pyclass(name = "_VCFIndexedReader")]
 pub struct VCFIndexedReader {
     path: String,
     batch_size: Option<usize>,
-    _runtime: Arc<Runtime>,
 }
 
 impl VCFIndexedReader {
@@ -46,12 +44,9 @@ impl VCFIndexedReader {
             ));
         }
 
-        let rt = Arc::new(Runtime::new()?);
-
         Ok(Self {
             path: path.to_string(),
             batch_size,
-            _runtime: rt,
         })
     }
 }
@@ -82,20 +77,19 @@ impl VCFIndexedReader {
         let options =
             ListingVCFTableOptions::new(FileCompressionType::GZIP, true).with_regions(vec![region]);
 
-        let df = self._runtime.block_on(async {
+        let runtime = get_tokio_runtime()?;
+
+        let df = runtime.block_on(async {
             match ctx.read_vcf(self.path.as_str(), options).await {
                 Ok(df) => Ok(df),
-                Err(e) => Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    format!("Error reading VCF file: {e}"),
-                )),
+                Err(e) => Err(io::Error::other(format!("Error reading VCF file: {e}"))),
             }
         })?;
 
-        let mut stream_ptr = self._runtime.block_on(async {
+        let mut stream_ptr = runtime.block_on(async {
             let stream = df.execute_stream().await?;
             let dataset_record_batch_stream =
-                DataFrameRecordBatchStream::new(stream, self._runtime.clone());
+                DataFrameRecordBatchStream::new(stream, runtime.clone());
 
             Ok::<_, BioBearError>(FFI_ArrowArrayStream::new(Box::new(
                 dataset_record_batch_stream,
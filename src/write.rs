@@ -0,0 +1,166 @@
+// Copyright 2024 WHERE TRUE Technologies.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::HashMap, sync::Arc};
+
+use arrow::array::{AsArray, RecordBatch};
+use arrow::datatypes::UInt64Type;
+use datafusion::{
+    common::{
+        config::{ConfigFileType, TableOptions},
+        file_options::{
+            csv_writer::CsvWriterOptions, json_writer::JsonWriterOptions,
+            parquet_writer::ParquetWriterOptions,
+        },
+        Result,
+    },
+    datasource::file_format::{
+        arrow::ArrowFormatFactory, csv::CsvFormatFactory, format_as_file_type,
+        json::JsonFormatFactory, parquet::ParquetFormatFactory, FileFormatFactory,
+    },
+    logical_expr::LogicalPlanBuilder,
+    prelude::DataFrame,
+};
+
+use crate::error::{BioBearError, BioBearResult};
+
+/// The file formats a result can be written as.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum WriteFormat {
+    Parquet,
+    Csv,
+    Json,
+    Ipc,
+}
+
+impl WriteFormat {
+    fn factory(self) -> Arc<dyn FileFormatFactory> {
+        match self {
+            Self::Parquet => Arc::new(ParquetFormatFactory::new()),
+            Self::Csv => Arc::new(CsvFormatFactory::new()),
+            Self::Json => Arc::new(JsonFormatFactory::new()),
+            Self::Ipc => Arc::new(ArrowFormatFactory::new()),
+        }
+    }
+
+    /// The format whose session options apply to the writer, if it has any.
+    fn config_format(self) -> Option<ConfigFileType> {
+        match self {
+            Self::Parquet => Some(ConfigFileType::PARQUET),
+            Self::Csv => Some(ConfigFileType::CSV),
+            Self::Json => Some(ConfigFileType::JSON),
+            Self::Ipc => None,
+        }
+    }
+}
+
+/// Options for writing a result, which override the session's options for the format, e.g.
+/// `datafusion.execution.parquet.compression`.
+#[derive(Debug, Default)]
+pub(crate) struct WriteOptions {
+    /// The compression codec, e.g. `zstd(3)` for Parquet or `gzip` for CSV and JSON.
+    pub(crate) compression: Option<String>,
+    /// The maximum number of rows in each Parquet row group.
+    pub(crate) row_group_size: Option<usize>,
+    /// The columns to partition the output by, hive-style, e.g. `chrom=1/`.
+    pub(crate) partition_by: Vec<String>,
+}
+
+impl WriteOptions {
+    /// The options as they're given to a `COPY` statement.
+    fn format_options(&self) -> HashMap<String, String> {
+        let mut options = HashMap::new();
+
+        if let Some(compression) = &self.compression {
+            options.insert("format.compression".to_string(), compression.clone());
+        }
+
+        if let Some(row_group_size) = self.row_group_size {
+            options.insert(
+                "format.max_row_group_size".to_string(),
+                row_group_size.to_string(),
+            );
+        }
+
+        options
+    }
+}
+
+/// Build a plan writing the DataFrame to the path, a local path or a URL of a registered object
+/// store, which streams the batches to the files as they're produced.
+///
+/// A path ending in `/`, or one that's partitioned, is written as a directory of files, otherwise
+/// it's written as a single file. Executing the plan returns the number of rows written.
+pub(crate) fn write_plan(
+    df: DataFrame,
+    path: &str,
+    format: WriteFormat,
+    options: WriteOptions,
+) -> BioBearResult<DataFrame> {
+    let (state, plan) = df.into_parts();
+    let format_options = options.format_options();
+
+    // Check the options up front, as the writer only reads them once the query is running.
+    if let Some(config_format) = format.config_format() {
+        let mut table_options = state.default_table_options();
+        table_options.set_config_format(config_format);
+
+        validate_writer_options(&mut table_options, &format_options)
+            .map_err(|e| BioBearError::InvalidConfiguration(e.to_string()))?;
+    }
+
+    let plan = LogicalPlanBuilder::copy_to(
+        plan,
+        path.to_string(),
+        format_as_file_type(format.factory()),
+        format_options,
+        options.partition_by,
+    )?
+    .build()?;
+
+    Ok(DataFrame::new(state, plan))
+}
+
+/// Apply the options to the session's options for the format, and check the writer accepts them,
+/// e.g. that the compression codec is valid.
+fn validate_writer_options(
+    table_options: &mut TableOptions,
+    format_options: &HashMap<String, String>,
+) -> Result<()> {
+    table_options.alter_with_string_hash_map(format_options)?;
+
+    match table_options.current_format {
+        Some(ConfigFileType::PARQUET) => {
+            ParquetWriterOptions::try_from(&table_options.parquet)?;
+        }
+        Some(ConfigFileType::CSV) => {
+            CsvWriterOptions::try_from(&table_options.csv)?;
+        }
+        Some(ConfigFileType::JSON) => {
+            JsonWriterOptions::try_from(&table_options.json)?;
+        }
+        None => {}
+    }
+
+    Ok(())
+}
+
+/// Sum the row counts returned by executing a write plan.
+pub(crate) fn rows_written(batches: &[RecordBatch]) -> usize {
+    batches
+        .iter()
+        .filter_map(|batch| batch.column(0).as_primitive_opt::<UInt64Type>())
+        .flat_map(|counts| counts.iter().flatten())
+        .sum::<u64>() as usize
+}